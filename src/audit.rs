@@ -0,0 +1,96 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use crate::gemini::Usage;
+
+/// One model call, exactly as it left the machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: String,
+    pub timestamp: i64,
+    pub command: String,
+    pub repo: String,
+    pub model: String,
+    /// The system instruction sent along with the prompt.
+    #[serde(default)]
+    pub system_instruction: Option<String>,
+    /// The prompt after redaction - what was actually sent.
+    pub prompt: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub usage: Usage,
+}
+
+/// Opt-in record of every model call, one JSON file per call in `~/.whisper/audit/`.
+pub struct AuditLog {
+    dir: PathBuf,
+    retention_days: i64,
+}
+
+impl AuditLog {
+    pub fn new(retention_days: i64) -> Result<Self> {
+        let home = dirs::home_dir().context("Could not find home directory")?;
+        let dir = home.join(".whisper").join("audit");
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir, retention_days })
+    }
+
+    /// Writes `entry` and drops anything older than the retention window.
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        let path = self.dir.join(format!("{}.json", entry.id));
+        fs::write(&path, serde_json::to_string_pretty(entry)?)
+            .with_context(|| format!("Failed to write audit entry {}", path.display()))?;
+
+        self.prune()
+    }
+
+    /// All entries, newest first.
+    pub fn list(&self) -> Result<Vec<AuditEntry>> {
+        let mut entries = Vec::new();
+
+        for file in fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let content = fs::read_to_string(&path)?;
+                let entry: AuditEntry = serde_json::from_str(&content)
+                    .with_context(|| format!("Corrupt audit entry {}", path.display()))?;
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| b.id.cmp(&a.id)));
+        Ok(entries)
+    }
+
+    /// Looks an entry up by id or unique id prefix.
+    pub fn get(&self, id: &str) -> Result<AuditEntry> {
+        let matches: Vec<AuditEntry> = self.list()?
+            .into_iter()
+            .filter(|entry| entry.id.starts_with(id))
+            .collect();
+
+        match matches.len() {
+            0 => anyhow::bail!("No audit entry matches '{}'", id),
+            1 => Ok(matches.into_iter().next().unwrap()),
+            n => anyhow::bail!("'{}' matches {} audit entries; use a longer id", id, n),
+        }
+    }
+
+    fn prune(&self) -> Result<()> {
+        if self.retention_days <= 0 {
+            return Ok(());
+        }
+
+        let cutoff = chrono::Utc::now().timestamp() - self.retention_days * 24 * 60 * 60;
+
+        for entry in self.list()? {
+            if entry.timestamp < cutoff {
+                fs::remove_file(self.dir.join(format!("{}.json", entry.id)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,65 +1,55 @@
 use anyhow::Result;
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
-use crate::workspace::Workspace;
-use crate::repository::GitAnalyzer;
-use crate::storage::MongoStore;
+use crate::workspace::{RepoEntry, Workspace};
+use crate::storage;
 use crate::config::Config;
+use crate::remote::CloneOptions;
+use super::sync;
 
-pub async fn run_add(repo: &str) -> Result<()> {
-    let is_url = repo.starts_with("http://") || repo.starts_with("https://") || repo.starts_with("git@");
+pub async fn run_add(repo: &str, options: &CloneOptions) -> Result<()> {
+    let is_url = ["http://", "https://", "ssh://", "git://", "git@"].iter().any(|prefix| repo.starts_with(prefix));
+    let manager = Workspace::new()?;
+    let config = Config::load_or_default()?;
     
     let (actual_path, repo_name, url) = if is_url {
-        println!("\n{}", "🌐 Cloning repository...".blue().bold());
-        
-        // Create permanent directory for cloned repos
-        let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let repos_dir = home.join(".whisper").join("repos");
-        std::fs::create_dir_all(&repos_dir)?;
-        
-        let name = repo.split('/').last().unwrap_or("repo").trim_end_matches(".git");
-        let clone_path = repos_dir.join(name);
-        
-        // Remove existing directory if it exists
-        if clone_path.exists() {
-            std::fs::remove_dir_all(&clone_path)?;
-        }
+        println!("\n{}", "🌐 Getting repository...".blue().bold());
         
-        let spinner = ProgressBar::new_spinner();
-        spinner.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
-        spinner.set_message("Cloning...");
+        // One directory per host/owner/name, so same-named repos never collide
+        let clone_path = manager.clone_dir(repo)?;
+        let name = clone_path.file_name().and_then(|n| n.to_str()).unwrap_or("repo").to_string();
         
-        git2::Repository::clone(repo, &clone_path)?;
-        spinner.finish_with_message("✅ Cloned".green().to_string());
+        // An earlier clone is fetched and fast-forwarded rather than downloaded again
+        sync::fetch_or_clone(&manager, repo, &clone_path, &config, options)?;
         
-        (clone_path.to_str().unwrap().to_string(), name.to_string(), Some(repo.to_string()))
+        (clone_path.to_string_lossy().to_string(), name, Some(repo.to_string()))
     } else {
+        if options.depth.is_some() || options.needs_git_cli() {
+            println!("{}", "⚠️  --depth, --since and --blobless only apply to cloned URLs; reading the local repository as is".yellow());
+        }
         let name = std::path::Path::new(repo).file_name().unwrap().to_str().unwrap();
         (repo.to_string(), name.to_string(), None)
     };
     
     println!("{}", format!("📁 Analyzing: {}", repo_name).cyan());
     
-    // Analyze
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_message("📚 Reading git history...");
-    let analyzer = GitAnalyzer::new(&actual_path)?;
-    let commits = analyzer.get_commits()?;
-    spinner.finish_with_message(format!("✅ Found {} commits", commits.len()).green().to_string());
+    let mut entry = RepoEntry {
+        managed_clone: is_url,
+        ..RepoEntry::new(repo_name, actual_path, url)
+    };
+    
+    // Re-adding a known repository only stores what's new since its last sync
+    if let Some(known) = manager.list_repos()?.into_iter().find(|r| r.id == entry.id || r.path == entry.path) {
+        entry.synced_head = known.synced_head;
+    }
     
-    // Store
-    let config = Config::load_or_setup().await?;
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_message("💾 Storing...");
-    let store = MongoStore::new(&config).await?;
-    store.save_commits(&commits).await?;
-    spinner.finish_with_message("✅ Stored".green().to_string());
+    let store = storage::open(&config).await?;
+    let entry = sync::ingest(store.as_ref(), entry).await?;
     
-    // Save to manager
-    let manager = Workspace::new()?;
-    manager.add_repo(repo_name.clone(), actual_path, url)?;
+    // Re-adding a known repository refreshes its entry instead of duplicating it
+    let entry = manager.add_repo(entry)?;
+    manager.set_active(&entry.id)?;
     
-    println!("\n{}", format!("✨ Repository '{}' added successfully!", repo_name).green().bold());
+    println!("\n{}", format!("✨ Repository '{}' added successfully!", entry.name).green().bold());
     println!("{}", "Now you can use: whisper summary, whisper demo, etc.".dimmed());
     
     Ok(())
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use colored::*;
+use crate::audit::AuditLog;
+use crate::config::Config;
+
+pub fn run_audit_list(limit: usize) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let log = AuditLog::new(config.audit_retention_days)?;
+    let entries = log.list()?;
+    
+    if !config.audit_log {
+        println!("{}", "ℹ️  Audit logging is off. Set AUDIT_LOG=true to record model calls.".dimmed());
+    }
+    
+    if entries.is_empty() {
+        println!("{}", "No audit entries recorded.".yellow());
+        return Ok(());
+    }
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", "  AUDIT LOG".cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    
+    for entry in entries.iter().take(limit) {
+        let status = if entry.error.is_some() { "failed".red() } else { "ok".green() };
+        println!(
+            "{}  {}  {:<10} {:<18} {:>7} tokens  {}",
+            entry.id.yellow(),
+            format_time(entry.timestamp),
+            entry.command,
+            entry.model,
+            entry.usage.total_token_count,
+            status
+        );
+        println!("   {}", entry.repo.dimmed());
+    }
+    
+    if entries.len() > limit {
+        println!("\n{}", format!("… {} older entries (use --limit to see more)", entries.len() - limit).dimmed());
+    }
+    println!();
+    
+    Ok(())
+}
+
+pub fn run_audit_show(id: &str) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let log = AuditLog::new(config.audit_retention_days)?;
+    let entry = log.get(id)?;
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", format!("  AUDIT ENTRY {}", entry.id).cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    println!("{} {}", "Time:   ".bold(), format_time(entry.timestamp));
+    println!("{} {}", "Command:".bold(), entry.command);
+    println!("{} {}", "Repo:   ".bold(), entry.repo);
+    println!("{} {}", "Model:  ".bold(), entry.model);
+    println!(
+        "{} {} prompt + {} response = {} total",
+        "Tokens: ".bold(),
+        entry.usage.prompt_token_count,
+        entry.usage.candidates_token_count,
+        entry.usage.total_token_count
+    );
+    
+    if let Some(system_instruction) = &entry.system_instruction {
+        println!("\n{}", "── System instruction ──".yellow().bold());
+        println!("{}", system_instruction);
+    }
+    
+    println!("\n{}", "── Prompt (as sent) ──".yellow().bold());
+    println!("{}", entry.prompt);
+    
+    match (&entry.response, &entry.error) {
+        (Some(response), _) => {
+            println!("\n{}", "── Response ──".green().bold());
+            println!("{}", response);
+        }
+        (None, Some(error)) => {
+            println!("\n{}", "── Error ──".red().bold());
+            println!("{}", error);
+        }
+        (None, None) => {}
+    }
+    println!();
+    
+    Ok(())
+}
+
+fn format_time(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
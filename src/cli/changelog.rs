@@ -1,22 +1,25 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use colored::*;
 use crate::workspace::Workspace;
-use crate::repository::GitAnalyzer;
-use crate::gemini::GeminiClient;
-use crate::config::Config;
+use crate::history;
+use crate::gemini::Narrative;
+use crate::narrator::Narrator;
+use super::GenerateOptions;
 
-pub async fn run_changelog(repo: Option<&str>) -> Result<()> {
-    let path = get_repo_path(repo)?;
+pub async fn run_changelog(repo: Option<&str>, options: &GenerateOptions) -> Result<()> {
+    let path = Workspace::new()?.resolve_path(repo)?;
     
     println!("\n{}", "📝 Generating changelog...".blue().bold());
     
-    let analyzer = GitAnalyzer::new(&path)?;
-    let commits = analyzer.get_commits()?;
+    let commits = history::load_commits(&path, &options.filter, None).await?;
     
-    let config = Config::load_or_setup().await?;
-    let client = GeminiClient::new(&config.gemini_api_key)?;
+    let narrator = Narrator::new("changelog", &path, options).await?;
     
-    let changelog = client.generate_changelog(&commits).await?;
+    if options.dry_run {
+        return narrator.dry_run(&Narrative::Changelog, &commits);
+    }
+    
+    let changelog = narrator.narrate(&Narrative::Changelog, &commits).await?;
     
     println!("\n{}", "═".repeat(60).cyan());
     println!("{}", "  CHANGELOG".cyan().bold());
@@ -25,13 +28,3 @@ pub async fn run_changelog(repo: Option<&str>) -> Result<()> {
     
     Ok(())
 }
-
-fn get_repo_path(repo: Option<&str>) -> Result<String> {
-    if let Some(path) = repo {
-        return Ok(path.to_string());
-    }
-    
-    let manager = Workspace::new()?;
-    let last = manager.get_last_repo()?.context("No repository added. Use 'whisper add <repo>'")?;
-    Ok(last.path)
-}
@@ -1,37 +1,30 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use crate::workspace::Workspace;
-use crate::repository::GitAnalyzer;
-use crate::gemini::GeminiClient;
-use crate::config::Config;
+use crate::history;
+use crate::gemini::Narrative;
+use crate::narrator::Narrator;
+use super::GenerateOptions;
 
-pub async fn run_demo(repo: Option<&str>) -> Result<()> {
-    let path = get_repo_path(repo)?;
+pub async fn run_demo(repo: Option<&str>, options: &GenerateOptions) -> Result<()> {
+    let path = Workspace::new()?.resolve_path(repo)?;
     
     println!("\n{}", "🎬 Generating demo script...".blue().bold());
     
-    let analyzer = GitAnalyzer::new(&path)?;
-    let mut commits = analyzer.get_commits()?;
+    let commits = history::load_commits(&path, &options.filter, Some(20)).await?;
     
-    // Take last 20 commits for demo
-    commits.truncate(20);
+    let narrator = Narrator::new("demo", &path, options).await?;
     
-    let config = Config::load_or_setup().await?;
-    let client = GeminiClient::new(&config.gemini_api_key)?;
+    if options.dry_run {
+        return narrator.dry_run(&Narrative::Demo, &commits);
+    }
     
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
     spinner.set_message("🤖 Crafting your demo script...");
     
-    let prompt = format!(
-        "Generate a 60-90 second demo script from these recent commits. \
-         Focus on the story: what problem was solved, key decisions, and impact. \
-         Make it conversational and demo-ready.\n\n{}",
-        format_commits(&commits)
-    );
-    
-    let demo = client.generate_content(&prompt).await?;
+    let demo = narrator.narrate(&Narrative::Demo, &commits).await?;
     spinner.finish_with_message("✅ Done".green().to_string());
     
     println!("\n{}", "═".repeat(60).cyan());
@@ -41,20 +34,3 @@ pub async fn run_demo(repo: Option<&str>) -> Result<()> {
     
     Ok(())
 }
-
-fn get_repo_path(repo: Option<&str>) -> Result<String> {
-    if let Some(path) = repo {
-        return Ok(path.to_string());
-    }
-    
-    let manager = Workspace::new()?;
-    let last = manager.get_last_repo()?.context("No repository added. Use 'whisper add <repo>'")?;
-    Ok(last.path)
-}
-
-fn format_commits(commits: &[crate::repository::Commit]) -> String {
-    commits.iter()
-        .map(|c| format!("- {}: {}", &c.hash[..7], c.message))
-        .collect::<Vec<_>>()
-        .join("\n")
-}
@@ -0,0 +1,165 @@
+use anyhow::{Result, Context};
+use colored::*;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use crate::config::Config;
+use crate::repository::{Commit, CommitFilter};
+use crate::storage::{self, Artifact, ArtifactQuery, CommitQuery, Store};
+use crate::workspace::{RepoEntry, Workspace};
+
+/// One line of a JSONL export.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record {
+    Repo(RepoEntry),
+    Commit(ExportedCommit),
+    Artifact(Artifact),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedCommit {
+    pub repo_id: String,
+    #[serde(flatten)]
+    pub commit: Commit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Repository, commits and saved outputs, one JSON record per line
+    Jsonl,
+    /// Commits only, one row each, for spreadsheets and warehouses
+    Csv,
+}
+
+pub async fn run_export(repo: Option<&str>, out: &str, format: ExportFormat, gzip: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let store = storage::open(&config).await?;
+    let entry = resolve_repo(store.as_ref(), repo).await?;
+    
+    println!("\n{}", format!("📤 Exporting '{}'...", entry.name).blue().bold());
+    
+    let commits = store
+        .find_commits(&CommitQuery {
+            repo_id: entry.id.clone(),
+            filter: CommitFilter::default(),
+            limit: None,
+        })
+        .await?;
+    
+    let artifacts = match format {
+        ExportFormat::Jsonl => {
+            store
+                .find_artifacts(&ArtifactQuery {
+                    repo_id: Some(entry.id.clone()),
+                    ..Default::default()
+                })
+                .await?
+        }
+        ExportFormat::Csv => Vec::new(),
+    };
+    
+    let file = BufWriter::new(File::create(out).with_context(|| format!("Failed to create {}", out))?);
+    let write = |writer: &mut dyn Write| match format {
+        ExportFormat::Jsonl => write_jsonl(writer, &entry, &commits, &artifacts),
+        ExportFormat::Csv => write_csv(writer, &entry, &commits),
+    };
+    
+    if gzip || out.ends_with(".gz") {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        write(&mut encoder)?;
+        encoder.finish()?.flush()?;
+    } else {
+        let mut file = file;
+        write(&mut file)?;
+        file.flush()?;
+    }
+    
+    let summary = match format {
+        ExportFormat::Jsonl => format!("{} commits and {} saved outputs", commits.len(), artifacts.len()),
+        ExportFormat::Csv => format!("{} commits", commits.len()),
+    };
+    
+    println!("{}", format!("✅ Wrote {} to {}", summary, out).green());
+    
+    Ok(())
+}
+
+pub(super) fn write_jsonl(writer: &mut dyn Write, entry: &RepoEntry, commits: &[Commit], artifacts: &[Artifact]) -> Result<()> {
+    let mut write = |record: &Record| -> Result<()> {
+        serde_json::to_writer(&mut *writer, record)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    };
+    
+    write(&Record::Repo(entry.clone()))?;
+    
+    for commit in commits {
+        write(&Record::Commit(ExportedCommit {
+            repo_id: entry.id.clone(),
+            commit: commit.clone(),
+        }))?;
+    }
+    
+    for artifact in artifacts {
+        write(&Record::Artifact(artifact.clone()))?;
+    }
+    
+    Ok(())
+}
+
+fn write_csv(writer: &mut dyn Write, entry: &RepoEntry, commits: &[Commit]) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(["repo_id", "repo", "hash", "author", "timestamp", "date", "kind", "subject", "message", "files"])?;
+    
+    for commit in commits {
+        let date = chrono::DateTime::from_timestamp(commit.timestamp, 0)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default();
+        let files = commit.files_changed
+            .iter()
+            .map(|f| f.file_path.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        
+        csv.write_record([
+            entry.id.as_str(),
+            entry.name.as_str(),
+            commit.hash.as_str(),
+            commit.author.as_str(),
+            &commit.timestamp.to_string(),
+            &date,
+            &commit.kind().name(),
+            commit.subject(),
+            commit.message.as_str(),
+            &files,
+        ])?;
+    }
+    
+    csv.flush()?;
+    Ok(())
+}
+
+/// The repository to export: a registered one by name, id or path (the active
+/// one if omitted), or one only known to the store.
+pub async fn resolve_repo(store: &dyn Store, repo: Option<&str>) -> Result<RepoEntry> {
+    let workspace = Workspace::new()?;
+    
+    let Some(repo) = repo else {
+        return workspace.require_active();
+    };
+    
+    if let Some(entry) = workspace.find(repo)? {
+        return Ok(entry);
+    }
+    
+    store
+        .list_repos()
+        .await?
+        .into_iter()
+        .rev()
+        .find(|entry| entry.name == repo || entry.id == repo || entry.path == repo)
+        .with_context(|| format!("No stored repository matches '{}'", repo))
+}
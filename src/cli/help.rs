@@ -9,21 +9,41 @@ pub fn show_welcome() {
     
     println!("\n{}", "Commands:".green().bold());
     println!("  whisper add <repo-url>        Add a repository for analysis");
+    println!("  whisper sync [--all]          Fetch and store new commits");
+    println!("  whisper repos list|use <name> Manage added repos and pick the active one");
     println!("  whisper summary               Full project story from git history");
     println!("  whisper demo                  60-90 second demo script");
     println!("  whisper last [count]          Explain recent work (default: 5 commits)");
     println!("  whisper since <ref>           Changes since commit/tag/date");
     println!("  whisper changelog             Clean changelog grouped by type");
+    println!("  whisper audit list|show <id>  Inspect what was sent to the model");
+    println!("  whisper stats [--json]        Commits per author/week, hot paths, type mix");
+    println!("  whisper history list|diff     Browse and compare past outputs");
+    println!("  whisper export|import         Move stored history between machines (JSONL/CSV)");
+    println!("  whisper store migrate         Copy project memory between SQLite and MongoDB");
     println!("  whisper setup                 Configure API keys and database");
     
     println!("\n{}", "Examples:".green().bold());
     println!("  whisper add https://github.com/user/repo");
+    println!("  whisper add https://github.com/user/big-repo --depth 500 --blobless");
     println!("  whisper summary");
     println!("  whisper demo");
     println!("  whisper last 10");
     println!("  whisper since v1.0.0");
     println!("  whisper since 2024-01-01");
     
+    println!("\n{}", "Options:".green().bold());
+    println!("  --offline                     Build output from commit data alone (no Gemini)");
+    println!("  --show-redactions             Show what was scrubbed from the prompt");
+    println!("  --show-prompt                 Print the rendered prompt before sending");
+    println!("  --dry-run                     Print the prompt and token estimate, then exit");
+    println!("  --audience <who>              exec, engineer, user, investor or custom");
+    println!("  --lang <code>                 Write generated content in another language");
+    println!("  --deep                        Let the model read diffs and files to explain why");
+    println!("  --with-stats                  Ground the narrative in stored statistics");
+    println!("  --author/--path/--kind        Only narrate matching commits");
+    println!("  --after/--before <date>       Only narrate commits in a date range (YYYY-MM-DD)");
+    
     println!("\n{}", "Quick mode:".yellow().bold());
     println!("  whisper <repo-url>            Add repo and show summary");
     
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use colored::*;
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use crate::config::Config;
+use crate::storage::{self, Artifact, ArtifactQuery, Store};
+use crate::workspace::{self, Workspace};
+
+pub async fn run_history_list(repo: Option<&str>, command: Option<&str>, limit: usize) -> Result<()> {
+    let store = open_store().await?;
+    let repo_id = repo.map(resolve_repo_id).transpose()?;
+    
+    let artifacts = store
+        .find_artifacts(&ArtifactQuery {
+            repo_id,
+            command: command.map(str::to_string),
+            id_prefix: None,
+            limit: Some(limit),
+        })
+        .await?;
+    
+    if artifacts.is_empty() {
+        println!("{}", "No generated outputs saved yet.".yellow());
+        return Ok(());
+    }
+    
+    let names: HashMap<String, String> = Workspace::new()?
+        .list_repos()?
+        .into_iter()
+        .map(|entry| (entry.id, entry.name))
+        .collect();
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", "  GENERATION HISTORY".cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    
+    for artifact in &artifacts {
+        println!(
+            "{}  {}  {:<10} {:<28} {:>4} commits",
+            artifact.id.yellow(),
+            format_time(artifact.timestamp),
+            artifact.command,
+            artifact.model,
+            artifact.commit_count
+        );
+        
+        let repo = names.get(&artifact.repo_id).cloned().unwrap_or_else(|| artifact.repo_id.clone());
+        let parameters = format_parameters(artifact);
+        if parameters.is_empty() {
+            println!("   {}", repo.dimmed());
+        } else {
+            println!("   {}  {}", repo.dimmed(), parameters.dimmed());
+        }
+    }
+    println!();
+    
+    Ok(())
+}
+
+pub async fn run_history_show(id: &str) -> Result<()> {
+    let store = open_store().await?;
+    let artifact = find_one(store.as_ref(), id).await?;
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", format!("  OUTPUT {}", artifact.id).cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    print_metadata(&artifact);
+    println!("\n{}\n", artifact.output);
+    
+    Ok(())
+}
+
+/// Line diff of two saved outputs, `old` first.
+pub async fn run_history_diff(old: &str, new: &str) -> Result<()> {
+    let store = open_store().await?;
+    let old = find_one(store.as_ref(), old).await?;
+    let new = find_one(store.as_ref(), new).await?;
+    
+    println!("\n{}", format!("--- {}  {}  {}", old.id, old.command, format_time(old.timestamp)).red());
+    println!("{}", format!("+++ {}  {}  {}", new.id, new.command, format_time(new.timestamp)).green());
+    
+    if old.model != new.model {
+        println!("{}", format!("    model: {} → {}", old.model, new.model).dimmed());
+    }
+    if old.commit_count != new.commit_count || old.last_commit != new.last_commit {
+        println!("{}", format!("    commits: {} → {}", old.commit_count, new.commit_count).dimmed());
+    }
+    if old.parameters != new.parameters {
+        println!("{}", format!("    parameters: [{}] → [{}]", format_parameters(&old), format_parameters(&new)).dimmed());
+    }
+    
+    if old.output == new.output {
+        println!("\n{}", "No differences in the generated text.".green());
+        return Ok(());
+    }
+    
+    // A missing final newline would otherwise show the last line as changed
+    let old_text = format!("{}\n", old.output.trim_end());
+    let new_text = format!("{}\n", new.output.trim_end());
+    let diff = TextDiff::from_lines(&old_text, &new_text);
+    
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        println!("\n{}", hunk.header().to_string().cyan());
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches('\n');
+            match change.tag() {
+                ChangeTag::Delete => println!("{}", format!("-{}", line).red()),
+                ChangeTag::Insert => println!("{}", format!("+{}", line).green()),
+                ChangeTag::Equal => println!(" {}", line.dimmed()),
+            }
+        }
+    }
+    println!();
+    
+    Ok(())
+}
+
+async fn open_store() -> Result<Box<dyn Store>> {
+    storage::open(&Config::load_or_default()?).await
+}
+
+/// A registered repository's id, looked up by name, id or path, else derived from the path.
+fn resolve_repo_id(repo: &str) -> Result<String> {
+    Ok(Workspace::new()?
+        .find(repo)?
+        .map(|entry| entry.id)
+        .unwrap_or_else(|| workspace::repo_id(repo, None)))
+}
+
+/// Looks an artifact up by id or unique id prefix.
+async fn find_one(store: &dyn Store, id: &str) -> Result<Artifact> {
+    let mut matches = store
+        .find_artifacts(&ArtifactQuery {
+            id_prefix: Some(id.to_string()),
+            limit: Some(2),
+            ..Default::default()
+        })
+        .await?;
+    
+    match matches.len() {
+        0 => anyhow::bail!("No saved output matches '{}'", id),
+        1 => Ok(matches.remove(0)),
+        _ => anyhow::bail!("'{}' matches several saved outputs; use a longer id", id),
+    }
+}
+
+fn print_metadata(artifact: &Artifact) {
+    let short = |hash: &Option<String>| hash.as_deref().map(|h| &h[..h.len().min(7)]).unwrap_or("-").to_string();
+    
+    println!("{} {}", "Time:      ".bold(), format_time(artifact.timestamp));
+    println!("{} {}", "Command:   ".bold(), artifact.command);
+    println!("{} {}", "Model:     ".bold(), artifact.model);
+    println!(
+        "{} {} ({}..{})",
+        "Commits:   ".bold(),
+        artifact.commit_count,
+        short(&artifact.first_commit),
+        short(&artifact.last_commit)
+    );
+    
+    let parameters = format_parameters(artifact);
+    if !parameters.is_empty() {
+        println!("{} {}", "Parameters:".bold(), parameters);
+    }
+}
+
+fn format_parameters(artifact: &Artifact) -> String {
+    artifact.parameters
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_time(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,171 @@
+use anyhow::{Result, Context};
+use colored::*;
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use crate::config::Config;
+use crate::repository::Commit;
+use crate::storage;
+use crate::workspace::{RepoEntry, Workspace};
+use super::export::Record;
+
+/// Loads a JSONL export (plain or gzipped) into the store. Everything is an
+/// upsert, so importing the same file twice is harmless.
+pub async fn run_import(file: &str) -> Result<()> {
+    if file.trim_end_matches(".gz").ends_with(".csv") {
+        anyhow::bail!("CSV exports are meant for spreadsheets and warehouses; import a JSONL export instead");
+    }
+    
+    println!("\n{}", format!("📥 Importing {}...", file).blue().bold());
+    
+    let mut repos: Vec<RepoEntry> = Vec::new();
+    let mut commits: BTreeMap<String, Vec<Commit>> = BTreeMap::new();
+    let mut artifacts = Vec::new();
+    
+    for record in read_records(file)? {
+        match record {
+            // Clone and sync state describe the exporting machine, not this one
+            Record::Repo(repo) => repos.push(RepoEntry {
+                managed_clone: false,
+                synced_head: None,
+                last_sync: 0,
+                ..repo
+            }),
+            Record::Commit(exported) => commits.entry(exported.repo_id).or_default().push(exported.commit),
+            Record::Artifact(artifact) => artifacts.push(artifact),
+        }
+    }
+    
+    let config = Config::load_or_default()?;
+    let store = storage::open(&config).await?;
+    let workspace = Workspace::new()?;
+    let known: Vec<String> = workspace.list_repos()?.into_iter().map(|entry| entry.id).collect();
+    
+    for repo in &repos {
+        store.save_repo(repo).await?;
+        
+        // Make it usable by name even though the clone lives on another machine
+        if !known.contains(&repo.id) {
+            workspace.add_repo(repo.clone())?;
+        }
+    }
+    
+    let mut total = 0;
+    for (repo_id, list) in &commits {
+        store.save_commits(repo_id, list, &|_, _| {}).await?;
+        total += list.len();
+    }
+    
+    for artifact in &artifacts {
+        store.save_artifact(artifact).await?;
+    }
+    
+    println!(
+        "{}",
+        format!(
+            "✅ Imported {} repositor{}, {} commits and {} saved outputs",
+            repos.len(),
+            if repos.len() == 1 { "y" } else { "ies" },
+            total,
+            artifacts.len()
+        ).green()
+    );
+    
+    Ok(())
+}
+
+/// Every record in a JSONL export, in file order.
+fn read_records(file: &str) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    
+    for (number, line) in open_reader(file)?.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        
+        records.push(
+            serde_json::from_str(&line)
+                .with_context(|| format!("{}:{}: not a whisper export record", file, number + 1))?,
+        );
+    }
+    
+    Ok(records)
+}
+
+/// Reads `file`, transparently un-gzipping it when it starts with the gzip magic bytes.
+fn open_reader(file: &str) -> Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(file).with_context(|| format!("Failed to open {}", file))?);
+    let gzipped = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    
+    Ok(if gzipped {
+        Box::new(BufReader::new(GzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use crate::repository::FileChange;
+    use crate::storage::Artifact;
+    use super::super::export::{write_jsonl, ExportedCommit};
+    
+    #[test]
+    fn export_reads_back_unchanged() {
+        let entry = RepoEntry {
+            id: "abc123".to_string(),
+            name: "demo".to_string(),
+            path: "/src/demo".to_string(),
+            url: Some("https://example.com/demo.git".to_string()),
+            added_at: 1704067200,
+            ..Default::default()
+        };
+        let commits = vec![Commit {
+            hash: "a1".to_string(),
+            message: "feat: login form\n\nWith a \"remember me\" box".to_string(),
+            author: "Ada Lovelace".to_string(),
+            timestamp: 1704067200,
+            files_changed: vec![FileChange { file_path: "src/login.rs".to_string(), summary: "+12 -3".to_string() }],
+        }];
+        let artifacts = vec![Artifact {
+            id: "art1".to_string(),
+            repo_id: entry.id.clone(),
+            command: "summary".to_string(),
+            output: "# Summary".to_string(),
+            ..Default::default()
+        }];
+        
+        let dir = tempfile::tempdir().unwrap();
+        for (name, gzip) in [("export.jsonl", false), ("export.jsonl.gz", true)] {
+            let path = dir.path().join(name);
+            let file = File::create(&path).unwrap();
+            if gzip {
+                let mut encoder = GzEncoder::new(file, Compression::default());
+                write_jsonl(&mut encoder, &entry, &commits, &artifacts).unwrap();
+                encoder.finish().unwrap().flush().unwrap();
+            } else {
+                let mut file = file;
+                write_jsonl(&mut file, &entry, &commits, &artifacts).unwrap();
+            }
+            
+            let records = read_records(path.to_str().unwrap()).unwrap();
+            let expected = [
+                Record::Repo(entry.clone()),
+                Record::Commit(ExportedCommit { repo_id: entry.id.clone(), commit: commits[0].clone() }),
+                Record::Artifact(artifacts[0].clone()),
+            ];
+            assert_eq!(
+                serde_json::to_value(&records).unwrap(),
+                serde_json::to_value(&expected).unwrap(),
+                "{} did not round-trip",
+                name
+            );
+        }
+    }
+}
@@ -1,29 +1,25 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use colored::*;
 use crate::workspace::Workspace;
-use crate::repository::GitAnalyzer;
-use crate::gemini::GeminiClient;
-use crate::config::Config;
+use crate::history;
+use crate::gemini::Narrative;
+use crate::narrator::Narrator;
+use super::GenerateOptions;
 
-pub async fn run_last(count: usize, repo: Option<&str>) -> Result<()> {
-    let path = get_repo_path(repo)?;
+pub async fn run_last(count: usize, repo: Option<&str>, options: &GenerateOptions) -> Result<()> {
+    let path = Workspace::new()?.resolve_path(repo)?;
     
     println!("\n{}", format!("🔍 Analyzing last {} commits...", count).blue().bold());
     
-    let analyzer = GitAnalyzer::new(&path)?;
-    let mut commits = analyzer.get_commits()?;
-    commits.truncate(count);
+    let commits = history::load_commits(&path, &options.filter, Some(count)).await?;
     
-    let config = Config::load_or_setup().await?;
-    let client = GeminiClient::new(&config.gemini_api_key)?;
+    let narrator = Narrator::new("last", &path, options).await?;
     
-    let prompt = format!(
-        "Explain the most recent work in this project and how it fits into the bigger picture. \
-         Focus on intent and impact, not implementation details.\n\n{}",
-        format_commits(&commits)
-    );
+    if options.dry_run {
+        return narrator.dry_run(&Narrative::Recent, &commits);
+    }
     
-    let explanation = client.generate_content(&prompt).await?;
+    let explanation = narrator.narrate(&Narrative::Recent, &commits).await?;
     
     println!("\n{}", "═".repeat(60).magenta());
     println!("{}", format!("  LAST {} COMMITS", count).magenta().bold());
@@ -32,20 +28,3 @@ pub async fn run_last(count: usize, repo: Option<&str>) -> Result<()> {
     
     Ok(())
 }
-
-fn get_repo_path(repo: Option<&str>) -> Result<String> {
-    if let Some(path) = repo {
-        return Ok(path.to_string());
-    }
-    
-    let manager = Workspace::new()?;
-    let last = manager.get_last_repo()?.context("No repository added. Use 'whisper add <repo>'")?;
-    Ok(last.path)
-}
-
-fn format_commits(commits: &[crate::repository::Commit]) -> String {
-    commits.iter()
-        .map(|c| format!("- {}: {}", &c.hash[..7], c.message))
-        .collect::<Vec<_>>()
-        .join("\n")
-}
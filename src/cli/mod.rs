@@ -6,3 +6,63 @@ pub mod demo;
 pub mod since;
 pub mod last;
 pub mod changelog;
+pub mod audit;
+pub mod store;
+pub mod history;
+pub mod export;
+pub mod import;
+pub mod stats;
+pub mod repos;
+pub mod sync;
+
+use anyhow::Result;
+use chrono::{NaiveDate, TimeZone, Utc};
+use crate::gemini::Audience;
+use crate::repository::CommitFilter;
+
+/// Flags shared by every command that generates a narrative.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct GenerateOptions {
+    /// Generate output from commit data alone, without calling Gemini
+    #[arg(long)]
+    pub offline: bool,
+    
+    /// Show what was redacted from prompts before they were sent
+    #[arg(long)]
+    pub show_redactions: bool,
+    
+    /// Print the fully rendered prompt before sending it
+    #[arg(long)]
+    pub show_prompt: bool,
+    
+    /// Print the prompt, token estimate and commit count, then exit without calling the API
+    #[arg(long)]
+    pub dry_run: bool,
+    
+    /// Who the output is written for
+    #[arg(long, value_enum)]
+    pub audience: Option<Audience>,
+    
+    /// Language for generated content, e.g. "de" or "ja" (default: OUTPUT_LANG or English)
+    #[arg(long, value_name = "CODE")]
+    pub lang: Option<String>,
+    
+    /// Let the model dig into diffs and files through tool calls to explain why things changed
+    #[arg(long)]
+    pub deep: bool,
+    
+    /// Ground narratives in aggregate numbers from the store (see `whisper stats`)
+    #[arg(long)]
+    pub with_stats: bool,
+    
+    #[command(flatten)]
+    pub filter: CommitFilter,
+}
+
+/// Parses a `YYYY-MM-DD` date into the Unix timestamp of its midnight (UTC).
+pub fn parse_date(value: &str) -> Result<i64> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Expected a date like 2024-01-31, got '{}'", value))?;
+    
+    Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).timestamp())
+}
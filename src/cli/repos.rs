@@ -0,0 +1,155 @@
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use colored::*;
+use crate::config::Config;
+use crate::repository::CommitFilter;
+use crate::storage::{self, ArtifactQuery, CommitQuery};
+use crate::workspace::{RepoEntry, Workspace};
+
+pub fn run_repos_list() -> Result<()> {
+    let workspace = Workspace::new()?;
+    let repos = workspace.list_repos()?;
+    
+    if repos.is_empty() {
+        println!("{}", "No repositories added yet. Use 'whisper add <repo>'".yellow());
+        return Ok(());
+    }
+    
+    let active = workspace.active_repo()?.map(|entry| entry.id);
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", "  REPOSITORIES".cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    
+    for entry in &repos {
+        let is_active = active.as_deref() == Some(entry.id.as_str());
+        let marker = if is_active { "●".green().to_string() } else { " ".to_string() };
+        let name = if is_active { entry.name.green().bold() } else { entry.name.normal() };
+        
+        println!("{} {:<24} {}", marker, name, entry.id.dimmed());
+        println!("   {}", entry.url.as_deref().unwrap_or(&entry.path).dimmed());
+    }
+    println!();
+    
+    Ok(())
+}
+
+pub fn run_repos_use(repo: &str) -> Result<()> {
+    let workspace = Workspace::new()?;
+    let entry = workspace.get(repo)?;
+    workspace.set_active(&entry.id)?;
+    
+    println!("{}", format!("✅ Commands now use '{}' when --repo is omitted", entry.name).green());
+    
+    Ok(())
+}
+
+pub fn run_repos_remove(repo: &str) -> Result<()> {
+    let workspace = Workspace::new()?;
+    let entry = workspace.get(repo)?;
+    let was_active = workspace.active_repo()?.is_some_and(|active| active.id == entry.id);
+    let removed = workspace.remove_repo(&entry.id)?;
+    
+    println!("{}", format!("✅ Removed '{}'", removed.name).green());
+    println!(
+        "{}",
+        format!("   Files at {} and its stored history were left in place.", removed.path).dimmed()
+    );
+    if was_active && !workspace.list_repos()?.is_empty() {
+        println!(
+            "{}",
+            "⚠️  It was the active repository. Pick another with 'whisper repos use <repo>'".yellow()
+        );
+    }
+    
+    Ok(())
+}
+
+pub async fn run_repos_rename(repo: &str, name: &str) -> Result<()> {
+    let workspace = Workspace::new()?;
+    let entry = workspace.get(repo)?;
+    let renamed = workspace.rename_repo(&entry.id, name)?;
+    
+    // Keep the store's copy in step, so exports and other machines see the new name
+    let saved = async {
+        storage::open(&Config::load_or_default()?).await?.save_repo(&renamed).await
+    }
+    .await;
+    if let Err(e) = saved {
+        println!("{}", format!("⚠️  Renamed locally, but the store was not updated ({})", e).yellow());
+    }
+    
+    println!("{}", format!("✅ Renamed '{}' to '{}'", entry.name, renamed.name).green());
+    
+    Ok(())
+}
+
+pub async fn run_repos_info(repo: Option<&str>) -> Result<()> {
+    let workspace = Workspace::new()?;
+    let entry = match repo {
+        Some(repo) => workspace.get(repo)?,
+        None => workspace.require_active()?,
+    };
+    let is_active = workspace.active_repo()?.is_some_and(|active| active.id == entry.id);
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", format!("  {}", entry.name).cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    println!("{} {}", "Id:".bold(), entry.id);
+    println!("{} {}", "Path:".bold(), entry.path);
+    if !std::path::Path::new(&entry.path).is_dir() {
+        println!("   {}", "(missing on disk; commands read the store instead)".yellow());
+    }
+    if let Some(url) = &entry.url {
+        println!("{} {}", "URL:".bold(), url);
+    }
+    println!("{} {}", "Added:".bold(), format_time(entry.added_at));
+    if entry.last_sync > 0 {
+        println!("{} {}", "Last sync:".bold(), format_time(entry.last_sync));
+    }
+    println!("{} {}", "Active:".bold(), if is_active { "yes" } else { "no" });
+    
+    match stored_summary(&entry).await {
+        Ok(summary) => println!("{} {}", "Stored:".bold(), summary),
+        Err(e) => println!("{} {}", "Stored:".bold(), format!("unavailable ({})", e).yellow()),
+    }
+    println!();
+    
+    Ok(())
+}
+
+/// Commit count, span and saved outputs for `entry` in the store.
+async fn stored_summary(entry: &RepoEntry) -> Result<String> {
+    let store = storage::open(&Config::load_or_default()?).await?;
+    let stats = store
+        .stats(
+            &CommitQuery {
+                repo_id: entry.id.clone(),
+                filter: CommitFilter::default(),
+                limit: None,
+            },
+            0,
+        )
+        .await?;
+    let outputs = store
+        .find_artifacts(&ArtifactQuery {
+            repo_id: Some(entry.id.clone()),
+            ..Default::default()
+        })
+        .await?
+        .len();
+    
+    let span = match (stats.first_commit_at, stats.last_commit_at) {
+        (Some(first), Some(last)) => format!(" ({} → {})", format_time(first), format_time(last)),
+        _ => String::new(),
+    };
+    
+    Ok(format!("{} commits{}, {} saved outputs", stats.commits, span, outputs))
+}
+
+fn format_time(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
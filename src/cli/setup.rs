@@ -5,7 +5,7 @@ use std::io::Write;
 
 use crate::config::Config;
 use crate::gemini::GeminiClient;
-use crate::storage::MongoStore;
+use crate::storage::{self, Backend, MongoStore};
 
 pub async fn run_setup() -> Result<()> {
     // Welcome banner
@@ -73,9 +73,82 @@ pub async fn run_setup() -> Result<()> {
         }
     }
 
-    // Step 3: MongoDB Configuration
+    // Step 3: Storage
     println!();
-    println!("{}", "┌─ Step 3: MongoDB Database Configuration ────────────────┐".bright_magenta());
+    println!("{}", "┌─ Step 3: Project Memory Storage ────────────────────────┐".bright_magenta());
+    println!("{}", "│                                                         │".bright_magenta());
+    println!("{}", "│ 💾 Commits are kept in a local database file by default │".bright_magenta());
+    println!("{}", "│    MongoDB is optional, e.g. for a shared team store    │".bright_magenta());
+    println!("{}", "│                                                         │".bright_magenta());
+    println!("{}", "└─────────────────────────────────────────────────────────┘".bright_magenta());
+
+    let use_mongodb = Confirm::new()
+        .with_prompt("🗄️  Use a shared MongoDB server instead of the local database?")
+        .default(Backend::parse(&config.store_backend)? == Backend::Mongodb)
+        .interact()?;
+
+    if use_mongodb {
+        config.store_backend = Backend::Mongodb.name().to_string();
+        configure_mongodb(&mut config).await?;
+    } else {
+        config.store_backend = Backend::Sqlite.name().to_string();
+        println!("{}", format!("✅ Using local database {}", config.store_path).green());
+    }
+
+    // Step 4: Test Storage
+    println!();
+    println!("{}", "┌─ Step 4: Testing Storage ───────────────────────────────┐".bright_green());
+    println!("{}", "│                                                         │".bright_green());
+    let target = if use_mongodb { mask_connection_string(&config.mongodb_url) } else { config.store_path.clone() };
+    println!("{}", format!("│ 🔍 Testing: {} │", target).bright_green());
+    println!("{}", "│                                                         │".bright_green());
+    println!("{}", "└─────────────────────────────────────────────────────────┘".bright_green());
+
+    match storage::open(&config).await {
+        Ok(_) => {
+            println!("{}", "✅ Storage ready".green().bold());
+        }
+        Err(e) => {
+            println!("{}", format!("❌ Storage check failed: {}", e).red());
+            println!("{}", "💡 Please check your storage configuration and try again.".yellow());
+            anyhow::bail!("Storage check failed");
+        }
+    }
+
+    // Step 5: Save Configuration
+    println!();
+    println!("{}", "┌─ Step 5: Saving Configuration ──────────────────────────┐".bright_white());
+    println!("{}", "│                                                         │".bright_white());
+
+    match config.save() {
+        Ok(_) => {
+            println!("{}", "│ 💾 Configuration saved to .env file                    │".bright_white());
+            println!("{}", "│                                                         │".bright_white());
+            println!("{}", "└─────────────────────────────────────────────────────────┘".bright_white());
+        }
+        Err(e) => {
+            println!("{}", format!("❌ Failed to save configuration: {}", e).red());
+            anyhow::bail!("Configuration save failed");
+        }
+    }
+
+    // Success message
+    println!();
+    println!("{}", "╔═══════════════════════════════════════╗".bright_green());
+    println!("{}", "║           🎉 Setup Complete!          ║".bright_green().bold());
+    println!("{}", "║                                       ║".bright_green());
+    println!("{}", "║ You're ready to analyze repositories! ║".bright_green());
+    println!("{}", "╚═══════════════════════════════════════╝".bright_green());
+    println!();
+    println!("{}", "💡 Try: whisper add <repo-url> or whisper <repo-url>".bright_cyan());
+    println!();
+
+    Ok(())
+}
+
+async fn configure_mongodb(config: &mut Config) -> Result<()> {
+    println!();
+    println!("{}", "┌─ MongoDB Database Configuration ────────────────────────┐".bright_magenta());
     println!("{}", "│                                                         │".bright_magenta());
 
     if config.mongodb_url.is_empty() || config.mongodb_url == "mongodb://localhost:27017/" {
@@ -108,7 +181,7 @@ pub async fn run_setup() -> Result<()> {
             // Validate the connection
             println!();
             println!("{}", "🔍 Validating connection...".cyan());
-            match MongoStore::new(&config).await {
+            match MongoStore::new(config).await {
                 Ok(_) => {
                     println!("{}", "✅ Connection validated successfully".green());
                 }
@@ -158,53 +231,6 @@ pub async fn run_setup() -> Result<()> {
         }
     }
 
-    // Step 4: Test MongoDB Connection
-    println!();
-    println!("{}", "┌─ Step 4: Testing Database Connection ────────────────────┐".bright_green());
-    println!("{}", "│                                                         │".bright_green());
-    println!("{}", format!("│ 🔍 Testing: {} │", mask_connection_string(&config.mongodb_url)).bright_green());
-    println!("{}", "│                                                         │".bright_green());
-    println!("{}", "└─────────────────────────────────────────────────────────┘".bright_green());
-
-    match MongoStore::new(&config).await {
-        Ok(_) => {
-            println!("{}", "✅ Database connection successful".green().bold());
-        }
-        Err(e) => {
-            println!("{}", format!("❌ Database connection failed: {}", e).red());
-            println!("{}", "💡 Please check your MongoDB configuration and try again.".yellow());
-            anyhow::bail!("Database connection failed");
-        }
-    }
-
-    // Step 5: Save Configuration
-    println!();
-    println!("{}", "┌─ Step 5: Saving Configuration ──────────────────────────┐".bright_white());
-    println!("{}", "│                                                         │".bright_white());
-
-    match config.save() {
-        Ok(_) => {
-            println!("{}", "│ 💾 Configuration saved to .env file                    │".bright_white());
-            println!("{}", "│                                                         │".bright_white());
-            println!("{}", "└─────────────────────────────────────────────────────────┘".bright_white());
-        }
-        Err(e) => {
-            println!("{}", format!("❌ Failed to save configuration: {}", e).red());
-            anyhow::bail!("Configuration save failed");
-        }
-    }
-
-    // Success message
-    println!();
-    println!("{}", "╔═══════════════════════════════════════╗".bright_green());
-    println!("{}", "║           🎉 Setup Complete!          ║".bright_green().bold());
-    println!("{}", "║                                       ║".bright_green());
-    println!("{}", "║ You're ready to analyze repositories! ║".bright_green());
-    println!("{}", "╚═══════════════════════════════════════╝".bright_green());
-    println!();
-    println!("{}", "💡 Try: whisper add <repo-url> or whisper <repo-url>".bright_cyan());
-    println!();
-
     Ok(())
 }
 
@@ -229,7 +255,7 @@ fn start_docker_mongodb() -> Result<()> {
 
     // Check if Docker daemon is running
     let daemon_check = Command::new("docker")
-        .args(&["info"])
+        .args(["info"])
         .output();
 
     if daemon_check.is_err() || !daemon_check.as_ref().unwrap().status.success() {
@@ -245,7 +271,7 @@ fn start_docker_mongodb() -> Result<()> {
     // Check if port 27017 is already in use
     println!("{}", "🔍 Checking if port 27017 is available...".cyan());
     let port_check = Command::new("lsof")
-        .args(&["-i", ":27017"])
+        .args(["-i", ":27017"])
         .output();
 
     if port_check.is_ok() && !port_check.as_ref().unwrap().stdout.is_empty() {
@@ -263,7 +289,7 @@ fn start_docker_mongodb() -> Result<()> {
     // Check if container exists
     println!("{}", "🔍 Checking for existing MongoDB container...".cyan());
     let container_check = Command::new("docker")
-        .args(&["ps", "-a", "--filter", "name=git-whisperer-mongo", "--format", "{{.Names}}"])
+        .args(["ps", "-a", "--filter", "name=git-whisperer-mongo", "--format", "{{.Names}}"])
         .output()?;
 
     let container_exists = String::from_utf8_lossy(&container_check.stdout).contains("git-whisperer-mongo");
@@ -271,7 +297,7 @@ fn start_docker_mongodb() -> Result<()> {
     if container_exists {
         // Check if container is running
         let status_check = Command::new("docker")
-            .args(&["ps", "--filter", "name=git-whisperer-mongo", "--format", "{{.Status}}"])
+            .args(["ps", "--filter", "name=git-whisperer-mongo", "--format", "{{.Status}}"])
             .output()?;
 
         let is_running = !String::from_utf8_lossy(&status_check.stdout).is_empty();
@@ -281,7 +307,7 @@ fn start_docker_mongodb() -> Result<()> {
         } else {
             println!("{}", "📦 MongoDB container exists but is stopped, starting it...".yellow());
             let start_result = Command::new("docker")
-                .args(&["start", "git-whisperer-mongo"])
+                .args(["start", "git-whisperer-mongo"])
                 .output()?;
 
             if !start_result.status.success() {
@@ -292,13 +318,13 @@ fn start_docker_mongodb() -> Result<()> {
 
                 // Remove the failed container
                 let _ = Command::new("docker")
-                    .args(&["rm", "-f", "git-whisperer-mongo"])
+                    .args(["rm", "-f", "git-whisperer-mongo"])
                     .output();
 
                 // Now try to create a new one
                 println!("{}", "📦 Creating fresh MongoDB container...".yellow());
                 let run_result = Command::new("docker")
-                    .args(&[
+                    .args([
                         "run", "-d",
                         "--name", "git-whisperer-mongo",
                         "-p", "27017:27017",
@@ -324,7 +350,7 @@ fn start_docker_mongodb() -> Result<()> {
     } else {
         println!("{}", "📦 Creating and starting MongoDB container...".yellow());
         let run_result = Command::new("docker")
-            .args(&[
+            .args([
                 "run", "-d",
                 "--name", "git-whisperer-mongo",
                 "-p", "27017:27017",
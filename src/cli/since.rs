@@ -1,21 +1,25 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use colored::*;
 use crate::workspace::Workspace;
-use crate::repository::GitAnalyzer;
-use crate::gemini::GeminiClient;
-use crate::config::Config;
+use crate::history;
+use crate::repository::CommitFilter;
+use crate::gemini::Narrative;
+use crate::narrator::Narrator;
+use super::GenerateOptions;
 
-pub async fn run_since(reference: &str, repo: Option<&str>) -> Result<()> {
-    let path = get_repo_path(repo)?;
+pub async fn run_since(reference: &str, repo: Option<&str>, options: &GenerateOptions) -> Result<()> {
+    let path = Workspace::new()?.resolve_path(repo)?;
     
     println!("\n{}", format!("📅 Changes since '{}'...", reference).blue().bold());
     
-    let analyzer = GitAnalyzer::new(&path)?;
-    let all_commits = analyzer.get_commits()?;
+    // The cut-off is found in the whole history; a filter could hide the
+    // reference commit itself
+    let all_commits = history::load_commits(&path, &CommitFilter::default(), None).await?;
     
     // Find commits since reference
     let since_commits: Vec<_> = all_commits.iter()
         .take_while(|c| !c.hash.starts_with(reference) && !c.message.contains(reference))
+        .filter(|c| options.filter.matches(c))
         .cloned()
         .collect();
     
@@ -24,16 +28,14 @@ pub async fn run_since(reference: &str, repo: Option<&str>) -> Result<()> {
         return Ok(());
     }
     
-    let config = Config::load_or_setup().await?;
-    let client = GeminiClient::new(&config.gemini_api_key)?;
+    let narrator = Narrator::new("since", &path, options).await?;
+    let narrative = Narrative::Since(reference.to_string());
     
-    let prompt = format!(
-        "Explain what changed since '{}' in plain English. Focus on features, fixes, and impact.\n\n{}",
-        reference,
-        format_commits(&since_commits)
-    );
+    if options.dry_run {
+        return narrator.dry_run(&narrative, &since_commits);
+    }
     
-    let explanation = client.generate_content(&prompt).await?;
+    let explanation = narrator.narrate(&narrative, &since_commits).await?;
     
     println!("\n{}", "═".repeat(60).green());
     println!("{}", format!("  CHANGES SINCE '{}'", reference).green().bold());
@@ -42,20 +44,3 @@ pub async fn run_since(reference: &str, repo: Option<&str>) -> Result<()> {
     
     Ok(())
 }
-
-fn get_repo_path(repo: Option<&str>) -> Result<String> {
-    if let Some(path) = repo {
-        return Ok(path.to_string());
-    }
-    
-    let manager = Workspace::new()?;
-    let last = manager.get_last_repo()?.context("No repository added. Use 'whisper add <repo>'")?;
-    Ok(last.path)
-}
-
-fn format_commits(commits: &[crate::repository::Commit]) -> String {
-    commits.iter()
-        .map(|c| format!("- {}: {}", &c.hash[..7], c.message))
-        .collect::<Vec<_>>()
-        .join("\n")
-}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use colored::*;
+use std::collections::HashMap;
+use crate::config::Config;
+use crate::repository::CommitFilter;
+use crate::storage::{self, CommitQuery, RepoStats};
+use super::export::resolve_repo;
+
+pub async fn run_stats(repo: Option<&str>, json: bool, top: usize, filter: &CommitFilter) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let store = storage::open(&config).await?;
+    let entry = resolve_repo(store.as_ref(), repo).await?;
+    
+    let stats = store
+        .stats(
+            &CommitQuery {
+                repo_id: entry.id.clone(),
+                filter: filter.clone(),
+                limit: None,
+            },
+            top,
+        )
+        .await?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    
+    if stats.commits == 0 {
+        println!("{}", format!("No stored commits match for '{}'.", entry.name).yellow());
+        return Ok(());
+    }
+    
+    println!("\n{}", "═".repeat(60).cyan());
+    println!("{}", format!("  STATS: {}", entry.name).cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    println!("{} {}", "Commits:".bold(), stats.commits);
+    println!("{} {}", "Active days:".bold(), stats.active_days);
+    println!(
+        "{} {} → {}",
+        "Span:".bold(),
+        format_date(stats.first_commit_at),
+        format_date(stats.last_commit_at)
+    );
+    
+    println!("\n{}", "👥 Commits per author per week".bold());
+    for row in &stats.authors_by_week {
+        println!("  {:<9} {:<32} {:>5}", row.week.dimmed(), row.author, row.commits);
+    }
+    
+    println!("\n{}", "📁 Most-changed paths".bold());
+    for row in &stats.top_paths {
+        println!("  {:>5}  {}", row.changes, row.path);
+    }
+    
+    println!("\n{}", "🏷  Commit types by month".bold());
+    for (month, mix) in kind_mix(&stats) {
+        println!("  {}  {}", month.dimmed(), mix);
+    }
+    println!();
+    
+    Ok(())
+}
+
+/// How many authors, weeks and months of detail `describe` puts in a prompt;
+/// a long-lived repository would otherwise add thousands of lines.
+const PROMPT_AUTHORS: usize = 10;
+const PROMPT_WEEKS: usize = 8;
+const PROMPT_MONTHS: usize = 12;
+
+/// The numbers as plain text, for grounding a narrative prompt (`--with-stats`).
+/// Per-author numbers are totals plus the most recent weeks, not every week.
+pub fn describe(stats: &RepoStats) -> String {
+    let mut lines = vec![
+        format!("Commits: {}", stats.commits),
+        format!("Active days: {}", stats.active_days),
+        format!(
+            "Span: {} to {}",
+            format_date(stats.first_commit_at),
+            format_date(stats.last_commit_at)
+        ),
+    ];
+    
+    let mut totals: HashMap<&str, i64> = HashMap::new();
+    for row in &stats.authors_by_week {
+        *totals.entry(row.author.as_str()).or_default() += row.commits;
+    }
+    let mut authors: Vec<(&str, i64)> = totals.into_iter().collect();
+    authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    
+    lines.push(format!("Authors: {}", authors.len()));
+    lines.push("Top authors by commits:".to_string());
+    lines.extend(
+        authors
+            .iter()
+            .take(PROMPT_AUTHORS)
+            .map(|(author, commits)| format!("- {}: {}", author, commits)),
+    );
+    
+    // Rows are sorted by week, so the recent ones are at the end
+    let mut weeks: Vec<&str> = stats.authors_by_week.iter().map(|row| row.week.as_str()).collect();
+    weeks.dedup();
+    let recent = &weeks[weeks.len().saturating_sub(PROMPT_WEEKS)..];
+    
+    lines.push(format!("Commits per author in the last {} active weeks:", recent.len()));
+    lines.extend(
+        stats.authors_by_week
+            .iter()
+            .filter(|row| recent.contains(&row.week.as_str()))
+            .map(|row| format!("- {} {}: {}", row.week, row.author, row.commits)),
+    );
+    
+    lines.push("Most-changed paths:".to_string());
+    lines.extend(stats.top_paths.iter().map(|row| format!("- {}: {} changes", row.path, row.changes)));
+    
+    let months = kind_mix(stats);
+    lines.push(format!("Commit types by month (last {}):", months.len().min(PROMPT_MONTHS)));
+    lines.extend(
+        months[months.len().saturating_sub(PROMPT_MONTHS)..]
+            .iter()
+            .map(|(month, mix)| format!("- {}: {}", month, mix)),
+    );
+    
+    lines.join("\n")
+}
+
+/// One `feature 3, fix 2` line per month, oldest first.
+fn kind_mix(stats: &RepoStats) -> Vec<(String, String)> {
+    let mut months: Vec<(String, Vec<String>)> = Vec::new();
+    
+    for row in &stats.kinds_by_month {
+        let entry = format!("{} {}", row.kind, row.commits);
+        match months.last_mut() {
+            Some((month, kinds)) if *month == row.month => kinds.push(entry),
+            _ => months.push((row.month.clone(), vec![entry])),
+        }
+    }
+    
+    months
+        .into_iter()
+        .map(|(month, kinds)| (month, kinds.join(", ")))
+        .collect()
+}
+
+fn format_date(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use crate::config::Config;
+use crate::repository::CommitFilter;
+use crate::storage::{self, ArtifactQuery, Backend, CommitQuery, Store};
+
+/// Copies every repository, its commits and saved outputs from one store backend to another.
+/// The source is left untouched; re-running is safe since writes are upserts.
+pub async fn run_store_migrate(from: Backend, to: Backend) -> Result<()> {
+    if from == to {
+        anyhow::bail!("--from and --to are both {}; nothing to migrate", from.name());
+    }
+    
+    let config = Config::load_or_default()?;
+    
+    println!("\n{}", format!("🚚 Migrating project memory: {} → {}", from.name(), to.name()).blue().bold());
+    
+    let source = storage::open_backend(from, &config).await?;
+    let target = storage::open_backend(to, &config).await?;
+    
+    let repos = source.list_repos().await?;
+    // Commits can outlive their repository entry, e.g. MongoDB's `legacy` ones
+    let orphans: Vec<String> = source
+        .commit_repo_ids()
+        .await?
+        .into_iter()
+        .filter(|id| !repos.iter().any(|repo| &repo.id == id))
+        .collect();
+    
+    if repos.is_empty() && orphans.is_empty() {
+        println!("{}", format!("No repositories stored in {}.", from.name()).yellow());
+        return Ok(());
+    }
+    
+    let mut total = 0;
+    
+    for repo in &repos {
+        target.save_repo(repo).await?;
+        total += copy_commits(source.as_ref(), target.as_ref(), &repo.id, &repo.name).await?;
+    }
+    
+    for id in &orphans {
+        let label = format!("{} (no repository entry)", id);
+        total += copy_commits(source.as_ref(), target.as_ref(), id, &label).await?;
+    }
+    
+    let artifacts = source.find_artifacts(&ArtifactQuery::default()).await?;
+    for artifact in &artifacts {
+        target.save_artifact(artifact).await?;
+    }
+    
+    println!(
+        "\n{}",
+        format!(
+            "✨ Migrated {} repositor{}, {} commits and {} saved outputs.",
+            repos.len() + orphans.len(),
+            if repos.len() + orphans.len() == 1 { "y" } else { "ies" },
+            total,
+            artifacts.len()
+        )
+            .green()
+            .bold()
+    );
+    
+    if Backend::parse(&config.store_backend)? != to {
+        println!("{}", format!("💡 Set STORE_BACKEND={} to use the migrated data.", to.name()).dimmed());
+    }
+    
+    Ok(())
+}
+
+/// Copies one repository's commits, showing progress under `label`.
+async fn copy_commits(source: &dyn Store, target: &dyn Store, repo_id: &str, label: &str) -> Result<usize> {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+    spinner.set_message(format!("📦 {}...", label));
+    
+    let commits = source
+        .find_commits(&CommitQuery {
+            repo_id: repo_id.to_string(),
+            filter: CommitFilter::default(),
+            limit: None,
+        })
+        .await?;
+    
+    target.save_commits(repo_id, &commits, &|stored, count| {
+        spinner.set_message(format!("📦 {}... {}/{}", label, stored, count));
+    }).await?;
+    
+    spinner.finish_with_message(format!("✅ {} ({} commits)", label, commits.len()).green().to_string());
+    Ok(commits.len())
+}
@@ -1,27 +1,30 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use crate::workspace::Workspace;
-use crate::repository::GitAnalyzer;
-use crate::gemini::GeminiClient;
-use crate::config::Config;
+use crate::history;
+use crate::gemini::Narrative;
+use crate::narrator::Narrator;
+use super::GenerateOptions;
 
-pub async fn run_summary(repo: Option<&str>) -> Result<()> {
-    let path = get_repo_path(repo)?;
+pub async fn run_summary(repo: Option<&str>, options: &GenerateOptions) -> Result<()> {
+    let path = Workspace::new()?.resolve_path(repo)?;
     
     println!("\n{}", "📖 Generating project summary...".blue().bold());
     
-    let analyzer = GitAnalyzer::new(&path)?;
-    let commits = analyzer.get_commits()?;
+    let commits = history::load_commits(&path, &options.filter, None).await?;
     
-    let config = Config::load_or_setup().await?;
-    let client = GeminiClient::new(&config.gemini_api_key)?;
+    let narrator = Narrator::new("summary", &path, options).await?;
+    
+    if options.dry_run {
+        return narrator.dry_run(&Narrative::Summary, &commits);
+    }
     
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
     spinner.set_message("🤖 AI is analyzing your project...");
     
-    let summary = client.generate_project_summary(&commits).await?;
+    let summary = narrator.narrate(&Narrative::Summary, &commits).await?;
     spinner.finish_with_message("✅ Done".green().to_string());
     
     println!("\n{}", "═".repeat(60).yellow());
@@ -31,13 +34,3 @@ pub async fn run_summary(repo: Option<&str>) -> Result<()> {
     
     Ok(())
 }
-
-fn get_repo_path(repo: Option<&str>) -> Result<String> {
-    if let Some(path) = repo {
-        return Ok(path.to_string());
-    }
-    
-    let manager = Workspace::new()?;
-    let last = manager.get_last_repo()?.context("No repository added. Use 'whisper add <repo>'")?;
-    Ok(last.path)
-}
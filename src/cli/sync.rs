@@ -0,0 +1,208 @@
+use anyhow::Result;
+use colored::*;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use std::path::Path;
+use crate::config::Config;
+use crate::remote::{self, CloneOptions, Transfer, Update};
+use crate::repository::GitAnalyzer;
+use crate::storage::{self, Store};
+use crate::workspace::{RepoEntry, Workspace};
+
+pub async fn run_sync(repo: Option<&str>, all: bool, full: bool) -> Result<()> {
+    let workspace = Workspace::new()?;
+    let entries = if all {
+        workspace.list_repos()?
+    } else {
+        let entry = match repo {
+            Some(repo) => workspace.get(repo)?,
+            None => workspace.require_active()?,
+        };
+        vec![entry]
+    };
+    
+    let config = Config::load_or_default()?;
+    let store = storage::open(&config).await?;
+    let mut failed = 0;
+    
+    for entry in &entries {
+        println!("\n{}", format!("🔄 Syncing '{}'...", entry.name).blue().bold());
+        
+        if let Err(e) = sync_repo(&workspace, store.as_ref(), &config, entry.clone(), full).await {
+            if !all {
+                return Err(e);
+            }
+            println!("{}", format!("❌ {:#}", e).red());
+            failed += 1;
+        }
+    }
+    
+    if failed > 0 {
+        anyhow::bail!("{} of {} repositories failed to sync", failed, entries.len());
+    }
+    
+    println!("\n{}", "✨ Sync complete".green().bold());
+    
+    Ok(())
+}
+
+async fn sync_repo(
+    workspace: &Workspace,
+    store: &dyn Store,
+    config: &Config,
+    mut entry: RepoEntry,
+    full: bool,
+) -> Result<()> {
+    match (entry.url.clone(), entry.managed_clone) {
+        (Some(url), true) => {
+            // Derived again rather than trusted, so a stored path can never
+            // point a clone outside ~/.whisper/repos
+            let dir = workspace.clone_dir(&url)?;
+            fetch_or_clone(workspace, &url, &dir, config, &CloneOptions::default())?;
+            entry.path = dir.to_string_lossy().to_string();
+        }
+        _ if !Path::new(&entry.path).is_dir() => {
+            anyhow::bail!("{} no longer exists; add the repository again from its new location", entry.path)
+        }
+        // Someone's own working copy: read what's there, never move their branches
+        _ => {}
+    }
+    
+    if full {
+        entry.synced_head = None;
+    }
+    
+    let entry = ingest(store, entry).await?;
+    workspace.add_repo(entry)?;
+    
+    Ok(())
+}
+
+/// Brings whisper's clone of `url` at `dir` up to date: fetch and fast-forward
+/// if it exists, clone it (as `options` say) otherwise. A rewritten remote
+/// history means a fresh clone; a directory whisper didn't create is never touched.
+pub fn fetch_or_clone(
+    workspace: &Workspace,
+    url: &str,
+    dir: &Path,
+    config: &Config,
+    options: &CloneOptions,
+) -> Result<()> {
+    if !dir.exists() {
+        return clone_into(workspace, url, dir, config, options);
+    }
+    
+    if !workspace.is_clone(dir) {
+        anyhow::bail!(
+            "{} already exists and was not cloned by whisper. Move it away and try again",
+            dir.display()
+        );
+    }
+    
+    let spinner = transfer_bar("🌐 Fetching...");
+    
+    match remote::update(dir, config, &|transfer| show_transfer(&spinner, transfer))? {
+        Update::UpToDate => finish_transfer(&spinner, "✅ Already up to date".green().to_string()),
+        Update::FastForwarded { from, to } => finish_transfer(
+            &spinner,
+            format!("✅ Fast-forwarded {}..{}", &from[..7], &to[..7]).green().to_string(),
+        ),
+        Update::Diverged => {
+            finish_transfer(
+                &spinner,
+                "⚠️  The remote history was rewritten; cloning again".yellow().to_string(),
+            );
+            workspace.remove_clone(dir)?;
+            clone_into(workspace, url, dir, config, options)?;
+        }
+    }
+    
+    Ok(())
+}
+
+fn clone_into(
+    workspace: &Workspace,
+    url: &str,
+    dir: &Path,
+    config: &Config,
+    options: &CloneOptions,
+) -> Result<()> {
+    if let Some(parent) = dir.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    
+    let spinner = transfer_bar("🌐 Cloning...");
+    remote::clone(url, dir, config, options, &|transfer| show_transfer(&spinner, transfer))?;
+    workspace.mark_clone(dir, url)?;
+    finish_transfer(&spinner, "✅ Cloned".green().to_string());
+    
+    Ok(())
+}
+
+/// Stores the commits made since `entry` was last synced (all of them the
+/// first time) and returns the entry with its new sync point.
+pub async fn ingest(store: &dyn Store, entry: RepoEntry) -> Result<RepoEntry> {
+    let spinner = new_spinner("📚 Reading git history...");
+    let analyzer = GitAnalyzer::new(&entry.path)?;
+    let head = analyzer.head()?;
+    // Without a sync point in the current history (first sync, --full, or a
+    // rewritten history) everything is stored afresh, replacing what was there
+    let known = entry.synced_head.as_deref().filter(|hash| analyzer.contains(hash));
+    let commits = analyzer.get_commits_after(known)?;
+    let found = if known.is_some() {
+        format!("✅ Found {} new commits", commits.len())
+    } else {
+        format!("✅ Found {} commits", commits.len())
+    };
+    spinner.finish_with_message(found.green().to_string());
+    
+    let spinner = new_spinner("💾 Storing...");
+    store.save_repo(&entry).await?;
+    if known.is_none() {
+        store.delete_commits(&entry.id).await?;
+    }
+    store.save_commits(&entry.id, &commits, &|stored, total| {
+        spinner.set_message(format!("💾 Storing... {}/{}", stored, total));
+    }).await?;
+    spinner.finish_with_message("✅ Stored".green().to_string());
+    
+    Ok(RepoEntry {
+        last_sync: chrono::Utc::now().timestamp(),
+        synced_head: Some(head),
+        ..entry
+    })
+}
+
+/// A bar for clone and fetch progress; it shows `message` until git reports a stage.
+fn transfer_bar(message: &'static str) -> ProgressBar {
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg:<36} [{bar:30.green}] {pos}/{len}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    bar.set_message(message);
+    bar
+}
+
+/// Ends a transfer bar with `message` on a plain spinner line, like the other steps.
+fn finish_transfer(bar: &ProgressBar, message: String) {
+    bar.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+    bar.finish_with_message(message);
+}
+
+fn show_transfer(bar: &ProgressBar, transfer: Transfer) {
+    bar.set_length(transfer.total as u64);
+    bar.set_position(transfer.done as u64);
+    bar.set_message(match transfer.bytes {
+        Some(bytes) if bytes > 0 => format!("🌐 {} ({})", transfer.stage, HumanBytes(bytes as u64)),
+        _ => format!("🌐 {}", transfer.stage),
+    });
+}
+
+fn new_spinner(message: &'static str) -> ProgressBar {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+    spinner.set_message(message);
+    spinner
+}
@@ -6,54 +6,95 @@ use colored::Colorize;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub gemini_api_key: String,
+    /// `sqlite` (default) or `mongodb`, from `STORE_BACKEND`.
+    pub store_backend: String,
+    /// Database file used by the `sqlite` backend.
+    pub store_path: String,
     pub mongodb_url: String,
     pub mongodb_db: String,
     pub mongodb_collection: String,
+    /// Database that holds the user's credentials, when not the one in `MONGODB_URL`.
+    pub mongodb_auth_source: String,
+    /// Connect over TLS even if `MONGODB_URL` doesn't ask for it.
+    pub mongodb_tls: bool,
+    /// PEM file of the certificate authorities to trust.
+    pub mongodb_tls_ca_file: String,
+    /// PEM file with the client certificate and its private key.
+    pub mongodb_tls_cert_key_file: String,
+    pub mongodb_server_selection_timeout_secs: u64,
+    pub mongodb_connect_timeout_secs: u64,
+    /// Name this client reports to the server, shown in its logs and `currentOp`.
+    pub mongodb_app_name: String,
+    /// Private key for SSH remotes, tried after ssh-agent.
+    pub git_ssh_key: String,
+    pub git_ssh_key_passphrase: String,
+    /// Personal access token for private HTTPS remotes.
+    pub git_token: String,
+    /// The only host `git_token` is sent to, and only over HTTPS.
+    pub git_token_host: String,
+    /// Username sent with `git_token` when the URL doesn't name one.
+    pub git_username: String,
     pub log_level: String,
+    pub llm_mode: String,
+    pub cassette_dir: String,
+    /// User-defined redaction rules as `(name, pattern)`, from `REDACT_RULE_<NAME>`.
+    pub redaction_rules: Vec<(String, String)>,
+    pub audit_log: bool,
+    pub audit_retention_days: i64,
+    /// Description of the audience used by `--audience custom`.
+    pub custom_audience: String,
+    /// Default language for generated content, e.g. `de` or `ja`. Empty means English.
+    pub output_lang: String,
+    /// Ordered `provider:model` fallback chain, from `LLM_MODELS`.
+    pub llm_models: Vec<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             gemini_api_key: String::new(),
+            store_backend: "sqlite".to_string(),
+            store_path: default_store_path(),
             mongodb_url: "mongodb://localhost:27017/".to_string(),
             mongodb_db: "git_whisperer_db".to_string(),
             mongodb_collection: "project_history".to_string(),
+            mongodb_auth_source: String::new(),
+            mongodb_tls: false,
+            mongodb_tls_ca_file: String::new(),
+            mongodb_tls_cert_key_file: String::new(),
+            mongodb_server_selection_timeout_secs: 5,
+            mongodb_connect_timeout_secs: 10,
+            mongodb_app_name: "git-whisperer".to_string(),
+            git_ssh_key: String::new(),
+            git_ssh_key_passphrase: String::new(),
+            git_token: String::new(),
+            git_token_host: "github.com".to_string(),
+            git_username: "x-access-token".to_string(),
             log_level: "INFO".to_string(),
+            llm_mode: "live".to_string(),
+            cassette_dir: default_cassette_dir(),
+            redaction_rules: Vec::new(),
+            audit_log: false,
+            audit_retention_days: 30,
+            custom_audience: String::new(),
+            output_lang: String::new(),
+            llm_models: vec![crate::gemini::DEFAULT_MODEL.to_string()],
         }
     }
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        dotenv::dotenv().ok();
-        
-        let gemini_api_key = std::env::var("GEMINI_API_KEY")
-            .context("GEMINI_API_KEY not found in environment")?;
-        
-        let mongodb_url = std::env::var("MONGODB_URL")
-            .unwrap_or_else(|_| "mongodb://localhost:27017/".to_string());
-        
-        let mongodb_db = std::env::var("MONGODB_DB")
-            .unwrap_or_else(|_| "git_whisperer_db".to_string());
-        
-        let mongodb_collection = std::env::var("MONGODB_COLLECTION")
-            .unwrap_or_else(|_| "project_history".to_string());
-        
-        let log_level = std::env::var("LOG_LEVEL")
-            .unwrap_or_else(|_| "INFO".to_string());
+        let config = Self::load_or_default()?;
+        if config.gemini_api_key.is_empty() {
+            anyhow::bail!("GEMINI_API_KEY not found in environment");
+        }
         
-        Ok(Self {
-            gemini_api_key,
-            mongodb_url,
-            mongodb_db,
-            mongodb_collection,
-            log_level,
-        })
+        Ok(config)
     }
     
     pub fn load_or_default() -> Result<Self> {
-        dotenv::dotenv().ok();
+        load_env_file()?;
         
         let gemini_api_key = std::env::var("GEMINI_API_KEY").unwrap_or_default();
         let mongodb_url = std::env::var("MONGODB_URL")
@@ -65,20 +106,53 @@ impl Config {
         let log_level = std::env::var("LOG_LEVEL")
             .unwrap_or_else(|_| "INFO".to_string());
         
+        let llm_mode = std::env::var("WHISPER_LLM_MODE")
+            .unwrap_or_else(|_| "live".to_string());
+        
+        let cassette_dir = std::env::var("WHISPER_CASSETTE_DIR")
+            .unwrap_or_else(|_| default_cassette_dir());
+        
         Ok(Self {
             gemini_api_key,
+            store_backend: std::env::var("STORE_BACKEND").unwrap_or_else(|_| "sqlite".to_string()),
+            store_path: std::env::var("STORE_PATH").unwrap_or_else(|_| default_store_path()),
             mongodb_url,
             mongodb_db,
             mongodb_collection,
+            mongodb_auth_source: std::env::var("MONGODB_AUTH_SOURCE").unwrap_or_default(),
+            mongodb_tls: env_flag("MONGODB_TLS"),
+            mongodb_tls_ca_file: std::env::var("MONGODB_TLS_CA_FILE").unwrap_or_default(),
+            mongodb_tls_cert_key_file: std::env::var("MONGODB_TLS_CERT_KEY_FILE").unwrap_or_default(),
+            mongodb_server_selection_timeout_secs: env_secs("MONGODB_SERVER_SELECTION_TIMEOUT", 5),
+            mongodb_connect_timeout_secs: env_secs("MONGODB_CONNECT_TIMEOUT", 10),
+            mongodb_app_name: std::env::var("MONGODB_APP_NAME").unwrap_or_else(|_| "git-whisperer".to_string()),
+            git_ssh_key: std::env::var("GIT_SSH_KEY").unwrap_or_default(),
+            git_ssh_key_passphrase: std::env::var("GIT_SSH_KEY_PASSPHRASE").unwrap_or_default(),
+            git_token: std::env::var("GIT_TOKEN").unwrap_or_default(),
+            git_token_host: std::env::var("GIT_TOKEN_HOST").unwrap_or_else(|_| "github.com".to_string()),
+            git_username: std::env::var("GIT_USERNAME").unwrap_or_else(|_| "x-access-token".to_string()),
             log_level,
+            llm_mode,
+            cassette_dir,
+            redaction_rules: redaction_rules_from_env(),
+            audit_log: env_flag("AUDIT_LOG"),
+            audit_retention_days: std::env::var("AUDIT_RETENTION_DAYS")
+                .ok()
+                .and_then(|days| days.trim().parse().ok())
+                .unwrap_or(30),
+            custom_audience: std::env::var("CUSTOM_AUDIENCE").unwrap_or_default(),
+            output_lang: std::env::var("OUTPUT_LANG").unwrap_or_default(),
+            llm_models: llm_models_from_env(),
         })
     }
     
     pub async fn load_or_setup() -> Result<Self> {
         let config = Self::load_or_default()?;
         
-        // Check if setup is needed - only if API key is missing
-        if config.gemini_api_key.is_empty() {
+        // Check if setup is needed - only if API key is missing.
+        // Replay mode never talks to the API, so it doesn't need one.
+        let replay = crate::gemini::LlmMode::parse(&config.llm_mode)? == crate::gemini::LlmMode::Replay;
+        if config.gemini_api_key.is_empty() && !replay {
             println!();
             println!("{}", "╔═══════════════════════════════════════╗".bright_yellow());
             println!("{}", "║           ⚠️  Setup Required          ║".bright_yellow().bold());
@@ -100,18 +174,193 @@ impl Config {
             "# Git Whisperer Configuration\n\
              # Auto-generated by setup process\n\n\
              GEMINI_API_KEY={}\n\
+             STORE_BACKEND={}\n\
+             STORE_PATH={}\n\
              MONGODB_URL={}\n\
              MONGODB_DB={}\n\
              MONGODB_COLLECTION={}\n\
-             LOG_LEVEL={}\n",
-            self.gemini_api_key,
-            self.mongodb_url,
-            self.mongodb_db,
-            self.mongodb_collection,
-            self.log_level
+             MONGODB_AUTH_SOURCE={}\n\
+             MONGODB_TLS={}\n\
+             MONGODB_TLS_CA_FILE={}\n\
+             MONGODB_TLS_CERT_KEY_FILE={}\n\
+             MONGODB_SERVER_SELECTION_TIMEOUT={}\n\
+             MONGODB_CONNECT_TIMEOUT={}\n\
+             MONGODB_APP_NAME={}\n\
+             GIT_SSH_KEY={}\n\
+             GIT_SSH_KEY_PASSPHRASE={}\n\
+             GIT_TOKEN={}\n\
+             GIT_TOKEN_HOST={}\n\
+             GIT_USERNAME={}\n\
+             LOG_LEVEL={}\n\
+             WHISPER_LLM_MODE={}\n\
+             WHISPER_CASSETTE_DIR={}\n\
+             AUDIT_LOG={}\n\
+             AUDIT_RETENTION_DAYS={}\n\
+             CUSTOM_AUDIENCE={}\n\
+             OUTPUT_LANG={}\n\
+             LLM_MODELS={}\n",
+            quote(&self.gemini_api_key),
+            quote(&self.store_backend),
+            quote(&self.store_path),
+            quote(&self.mongodb_url),
+            quote(&self.mongodb_db),
+            quote(&self.mongodb_collection),
+            quote(&self.mongodb_auth_source),
+            self.mongodb_tls,
+            quote(&self.mongodb_tls_ca_file),
+            quote(&self.mongodb_tls_cert_key_file),
+            self.mongodb_server_selection_timeout_secs,
+            self.mongodb_connect_timeout_secs,
+            quote(&self.mongodb_app_name),
+            quote(&self.git_ssh_key),
+            quote(&env_file_secret("GIT_SSH_KEY_PASSPHRASE")),
+            quote(&env_file_secret("GIT_TOKEN")),
+            quote(&self.git_token_host),
+            quote(&self.git_username),
+            quote(&self.log_level),
+            quote(&self.llm_mode),
+            quote(&self.cassette_dir),
+            self.audit_log,
+            self.audit_retention_days,
+            quote(&self.custom_audience),
+            quote(&self.output_lang),
+            quote(&self.llm_models.join(","))
         );
         
+        let mut env_content = env_content;
+        for (name, pattern) in &self.redaction_rules {
+            env_content.push_str(&format!("REDACT_RULE_{}={}\n", name.to_uppercase(), quote(pattern)));
+        }
+        
         fs::write(".env", env_content)?;
         Ok(())
     }
 }
+
+/// Loads `.env` if there is one. A line dotenv can't parse is an error: it
+/// would otherwise silently drop that setting and every one after it.
+fn load_env_file() -> Result<()> {
+    match dotenv::dotenv() {
+        Ok(_) => Ok(()),
+        Err(dotenv::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        // The value may be a secret, so only say where it is
+        Err(dotenv::Error::LineParse(value, _)) => anyhow::bail!(
+            "Invalid value for {} in .env. Quote values containing spaces or backslashes with single quotes",
+            env_key_of(&value).unwrap_or_else(|| "a setting".to_string())
+        ),
+        Err(e) => Err(e).context("Failed to read .env"),
+    }
+}
+
+/// The name of the `.env` setting whose value is `value`, if it can be found.
+fn env_key_of(value: &str) -> Option<String> {
+    let content = fs::read_to_string(".env").ok()?;
+    content.lines().find_map(|line| {
+        let (key, rest) = line.split_once('=')?;
+        (rest.trim() == value.trim()).then(|| key.trim().trim_start_matches("export ").to_string())
+    })
+}
+
+/// The value `.env` already holds for `key`, if any. Git secrets are never
+/// entered in the setup wizard, so one that only lives in the shell
+/// environment must not be copied into the file.
+// `from_path_iter` is the only way to read the file without the environment shadowing it
+#[allow(deprecated)]
+fn env_file_secret(key: &str) -> String {
+    dotenv::from_path_iter(".env")
+        .ok()
+        .and_then(|mut vars| vars.find_map(|var| var.ok().filter(|(name, _)| name == key)))
+        .map(|(_, value)| value)
+        .unwrap_or_default()
+}
+
+/// Single-quotes `value` for `.env`, so dotenv takes backslashes and spaces literally.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn default_store_path() -> String {
+    dirs::home_dir()
+        .map(|home| home.join(".whisper").join("whisper.db"))
+        .unwrap_or_else(|| std::path::PathBuf::from(".whisper/whisper.db"))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn default_cassette_dir() -> String {
+    dirs::home_dir()
+        .map(|home| home.join(".whisper").join("cassettes"))
+        .unwrap_or_else(|| std::path::PathBuf::from(".whisper/cassettes"))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Collects every `REDACT_RULE_<NAME>=<regex>` variable, sorted by name.
+fn redaction_rules_from_env() -> Vec<(String, String)> {
+    let mut rules: Vec<(String, String)> = std::env::vars()
+        .filter_map(|(key, value)| {
+            key.strip_prefix("REDACT_RULE_")
+                .filter(|name| !name.is_empty() && !value.is_empty())
+                .map(|name| (name.to_string(), value))
+        })
+        .collect();
+    rules.sort();
+    rules
+}
+
+fn env_flag(key: &str) -> bool {
+    std::env::var(key)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// A whole number of seconds from `key`, or `default` when unset or invalid.
+fn env_secs(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+fn llm_models_from_env() -> Vec<String> {
+    let models: Vec<String> = std::env::var("LLM_MODELS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    
+    if models.is_empty() {
+        vec![crate::gemini::DEFAULT_MODEL.to_string()]
+    } else {
+        models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    #[allow(deprecated)]
+    fn quoted_values_survive_dotenv() {
+        let values = ["correct horse", r"[a-z0-9-]+\.internal\.example\.com", "it's", "$HOME", "a#b", "", "C:\\keys\\id_rsa"];
+        let path = std::env::temp_dir().join(format!("whisper-quote-test-{}.env", std::process::id()));
+        let content: String = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("KEY_{}={}\n", i, quote(value)))
+            .collect();
+        fs::write(&path, content).unwrap();
+        
+        let parsed: Vec<(String, String)> = dotenv::from_path_iter(&path)
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(parsed[i], (format!("KEY_{}", i), value.to_string()));
+        }
+    }
+}
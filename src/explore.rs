@@ -0,0 +1,102 @@
+use serde_json::{json, Value};
+use crate::repository::GitAnalyzer;
+
+const MAX_DIFF_CHARS: usize = 12_000;
+const MAX_FILE_CHARS: usize = 16_000;
+const MAX_LISTED_FILES: usize = 400;
+const MAX_SEARCH_RESULTS: usize = 20;
+
+/// Function declarations for the repository tools offered in `--deep` mode.
+pub fn declarations() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "show_commit",
+            "description": "Show the full message, author and diff of one commit.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "revision": { "type": "string", "description": "Commit hash (full or short), tag or branch." }
+                },
+                "required": ["revision"]
+            }
+        }),
+        json!({
+            "name": "list_files",
+            "description": "List the files that exist at a revision, optionally under a directory.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "revision": { "type": "string", "description": "Commit hash, tag or branch. Use HEAD for the latest state." },
+                    "path_prefix": { "type": "string", "description": "Only list paths starting with this prefix, e.g. 'src/'." }
+                },
+                "required": ["revision"]
+            }
+        }),
+        json!({
+            "name": "read_file",
+            "description": "Read a file as it was at a revision.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "revision": { "type": "string", "description": "Commit hash, tag or branch." },
+                    "path": { "type": "string", "description": "File path relative to the repository root." }
+                },
+                "required": ["revision", "path"]
+            }
+        }),
+        json!({
+            "name": "search_commits",
+            "description": "Find commits whose message or changed file paths contain a search term.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Case-insensitive search term." }
+                },
+                "required": ["query"]
+            }
+        }),
+    ]
+}
+
+/// Runs the tool `name` against the repository. Failures are reported back
+/// to the model as `{"error": ...}` so it can correct itself.
+pub fn execute(analyzer: &GitAnalyzer, name: &str, args: &Value) -> Value {
+    let arg = |key: &str| args.get(key).and_then(Value::as_str).unwrap_or("");
+
+    let result = match name {
+        "show_commit" => analyzer
+            .show_commit(arg("revision"), MAX_DIFF_CHARS)
+            .map(|text| json!({ "commit": text })),
+        "list_files" => {
+            let prefix = Some(arg("path_prefix")).filter(|p| !p.is_empty());
+            analyzer
+                .list_files(arg("revision"), prefix, MAX_LISTED_FILES)
+                .map(|files| json!({ "files": files }))
+        }
+        "read_file" => analyzer
+            .read_file(arg("revision"), arg("path"), MAX_FILE_CHARS)
+            .map(|text| json!({ "content": text })),
+        "search_commits" => analyzer
+            .search_commits(arg("query"), MAX_SEARCH_RESULTS)
+            .map(|commits| {
+                let commits: Vec<Value> = commits
+                    .iter()
+                    .map(|c| json!({ "hash": c.short_hash(), "author": c.author, "message": c.subject() }))
+                    .collect();
+                json!({ "commits": commits })
+            }),
+        other => Err(anyhow::anyhow!("Unknown tool '{}'", other)),
+    };
+
+    result.unwrap_or_else(|e| json!({ "error": format!("{:#}", e) }))
+}
+
+/// One-line description of a tool call, for progress output.
+pub fn describe(name: &str, args: &Value) -> String {
+    let values: Vec<String> = args
+        .as_object()
+        .map(|map| map.values().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    format!("{}({})", name, values.join(", "))
+}
@@ -0,0 +1,56 @@
+use colored::*;
+use regex::Regex;
+use crate::repository::Commit;
+
+/// Phrases that read like instructions to a language model rather than a
+/// description of a code change.
+const SUSPICIOUS_PATTERNS: &[(&str, &str)] = &[
+    ("override", r"(?i)\b(ignore|disregard|forget|override)\b.{0,40}\b(previous|prior|above|earlier|all|system|these)\b.{0,20}\b(instructions?|prompts?|rules|context)\b"),
+    ("role change", r"(?i)\b(you are now|act as|pretend to be|from now on,? you|new instructions?)\b"),
+    ("prompt probe", r"(?i)\b(system prompt|reveal (your|the) (instructions|prompt)|print (your|the) (instructions|prompt))\b"),
+    ("output control", r"(?i)\b(respond|reply|answer|output) only with\b|\bdo not (summari[sz]e|mention|report)\b"),
+    ("fence escape", r"(?i)</?\s*(commit_data|system|instructions?)\s*>"),
+];
+
+/// A commit whose message looks like an attempt to steer the model.
+pub struct InjectionWarning {
+    pub hash: String,
+    pub reason: &'static str,
+    pub excerpt: String,
+}
+
+pub fn scan_commits(commits: &[Commit]) -> Vec<InjectionWarning> {
+    let patterns: Vec<(&str, Regex)> = SUSPICIOUS_PATTERNS
+        .iter()
+        .map(|(reason, pattern)| (*reason, Regex::new(pattern).expect("guard pattern is valid")))
+        .collect();
+    
+    let mut warnings = Vec::new();
+    
+    for commit in commits {
+        if let Some((reason, found)) = patterns
+            .iter()
+            .find_map(|(reason, regex)| regex.find(&commit.message).map(|m| (*reason, m.as_str())))
+        {
+            warnings.push(InjectionWarning {
+                hash: commit.short_hash().to_string(),
+                reason,
+                excerpt: found.chars().take(80).collect(),
+            });
+        }
+    }
+    
+    warnings
+}
+
+pub fn print_warnings(warnings: &[InjectionWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    
+    println!("\n{}", "⚠️  Suspicious commit messages (possible prompt injection):".yellow().bold());
+    for warning in warnings {
+        println!("   {} [{}] \"{}\"", warning.hash.yellow(), warning.reason, warning.excerpt);
+    }
+    println!("{}", "   These were passed to the model as data only; review the output with care.".dimmed());
+}
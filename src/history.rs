@@ -0,0 +1,93 @@
+use anyhow::Result;
+use colored::*;
+use crate::config::Config;
+use crate::repository::{Commit, CommitFilter, GitAnalyzer};
+use crate::storage::{self, CommitQuery, Store};
+use crate::workspace::{RepoEntry, Workspace};
+
+/// Commits of the repository at `path`, newest first.
+///
+/// Read from the project memory store when the repository was added there,
+/// so a deleted or remote clone still works; walked from git otherwise.
+/// Commits made in the clone since it was last synced are stored first.
+pub async fn load_commits(path: &str, filter: &CommitFilter, limit: Option<usize>) -> Result<Vec<Commit>> {
+    let workspace = Workspace::new()?;
+    if let Some(entry) = workspace.find_by_path(path)? {
+        match from_store(&workspace, entry, filter, limit).await {
+            Ok(commits) if !commits.is_empty() => {
+                println!("{}", format!("📦 Read {} commits from project memory", commits.len()).dimmed());
+                return Ok(commits);
+            }
+            Ok(_) => {}
+            Err(e) => println!(
+                "{}",
+                format!("⚠️  Project memory unavailable, reading git history instead ({})", e).yellow()
+            ),
+        }
+    }
+    
+    let analyzer = GitAnalyzer::new(path)?;
+    let mut commits: Vec<Commit> = analyzer
+        .get_commits()?
+        .into_iter()
+        .filter(|c| filter.matches(c))
+        .collect();
+    
+    if let Some(limit) = limit {
+        commits.truncate(limit);
+    }
+    
+    Ok(commits)
+}
+
+async fn from_store(
+    workspace: &Workspace,
+    entry: RepoEntry,
+    filter: &CommitFilter,
+    limit: Option<usize>,
+) -> Result<Vec<Commit>> {
+    let config = Config::load_or_default()?;
+    let store = storage::open(&config).await?;
+    let query = CommitQuery {
+        repo_id: entry.id.clone(),
+        filter: filter.clone(),
+        limit,
+    };
+    
+    catch_up(workspace, store.as_ref(), entry).await?;
+    store.find_commits(&query).await
+}
+
+/// Stores the commits made in `entry`'s clone since its last sync, so local
+/// work shows up without a `whisper sync`. A missing clone is left to the store.
+async fn catch_up(workspace: &Workspace, store: &dyn Store, entry: RepoEntry) -> Result<()> {
+    let Ok(analyzer) = GitAnalyzer::new(&entry.path) else {
+        return Ok(());
+    };
+    let head = analyzer.head()?;
+    if entry.synced_head.as_deref() == Some(head.as_str()) {
+        return Ok(());
+    }
+    
+    // A rewritten history replaces the stored one rather than adding to it
+    let known = entry.synced_head.as_deref().filter(|hash| analyzer.contains(hash));
+    let commits = analyzer.get_commits_after(known)?;
+    let replaced = known.is_none() && entry.synced_head.is_some();
+    if known.is_none() {
+        store.delete_commits(&entry.id).await?;
+    }
+    store.save_commits(&entry.id, &commits, &|_, _| {}).await?;
+    let entry = workspace.add_repo(RepoEntry {
+        last_sync: chrono::Utc::now().timestamp(),
+        synced_head: Some(head),
+        ..entry
+    })?;
+    
+    if replaced {
+        println!("{}", format!("📥 History of {} was rewritten; stored its {} commits afresh", entry.path, commits.len()).dimmed());
+    } else if !commits.is_empty() {
+        println!("{}", format!("📥 Stored {} new commits from {}", commits.len(), entry.path).dimmed());
+    }
+    
+    Ok(())
+}
@@ -1,16 +1,25 @@
 use clap::{Parser, Subcommand};
 use anyhow::Result;
 
+mod audit;
 mod cli;
 mod repository;
 mod config;
+mod explore;
 mod gemini;
+mod guard;
+mod history;
+mod narrator;
+mod offline;
+mod redact;
+mod remote;
 mod storage;
 mod workspace;
 
 #[derive(Parser)]
 #[command(name = "whisper")]
 #[command(about = "Turn commit history into human stories", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
@@ -18,6 +27,12 @@ struct Cli {
     /// Path to git repository or URL
     #[arg(value_name = "REPO_PATH")]
     repo_path: Option<String>,
+    
+    #[command(flatten)]
+    clone: remote::CloneOptions,
+    
+    #[command(flatten)]
+    options: cli::GenerateOptions,
 }
 
 #[derive(Subcommand)]
@@ -29,20 +44,29 @@ enum Commands {
     Add {
         /// Repository URL or path
         repo: String,
+        
+        #[command(flatten)]
+        clone: remote::CloneOptions,
     },
     
     /// Generate full project summary from entire git history
     Summary {
-        /// Repository path (uses last added if omitted)
+        /// Repository name or path (uses the active repository if omitted)
         #[arg(short, long)]
         repo: Option<String>,
+        
+        #[command(flatten)]
+        options: cli::GenerateOptions,
     },
     
     /// Generate a 60-90 second demo script from recent commits
     Demo {
-        /// Repository path (uses last added if omitted)
+        /// Repository name or path (uses the active repository if omitted)
         #[arg(short, long)]
         repo: Option<String>,
+        
+        #[command(flatten)]
+        options: cli::GenerateOptions,
     },
     
     /// Explain what changed since a commit, tag, or date
@@ -50,9 +74,12 @@ enum Commands {
         /// Commit hash, tag, or date (e.g., "v1.0.0", "2024-01-01")
         reference: String,
         
-        /// Repository path (uses last added if omitted)
+        /// Repository name or path (uses the active repository if omitted)
         #[arg(short, long)]
         repo: Option<String>,
+        
+        #[command(flatten)]
+        options: cli::GenerateOptions,
     },
     
     /// Explain the most recent work and how it fits the bigger picture
@@ -61,50 +88,266 @@ enum Commands {
         #[arg(short, long, default_value = "5")]
         count: usize,
         
-        /// Repository path (uses last added if omitted)
+        /// Repository name or path (uses the active repository if omitted)
         #[arg(short, long)]
         repo: Option<String>,
+        
+        #[command(flatten)]
+        options: cli::GenerateOptions,
     },
     
     /// Generate a clean changelog grouped by features, fixes, and refactors
     Changelog {
-        /// Repository path (uses last added if omitted)
+        /// Repository name or path (uses the active repository if omitted)
+        #[arg(short, long)]
+        repo: Option<String>,
+        
+        #[command(flatten)]
+        options: cli::GenerateOptions,
+    },
+    
+    /// Inspect the log of everything sent to the model
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    
+    /// Write a repository's stored history and saved outputs to a file
+    Export {
+        /// Repository name, id or path (uses the active repository if omitted)
+        #[arg(short, long)]
+        repo: Option<String>,
+        
+        /// File to write; a `.gz` suffix compresses it
+        #[arg(short, long)]
+        out: String,
+        
+        /// jsonl (everything, re-importable) or csv (commits only)
+        #[arg(short, long, value_enum, default_value = "jsonl")]
+        format: cli::export::ExportFormat,
+        
+        /// Compress the output with gzip
+        #[arg(long)]
+        gzip: bool,
+    },
+    
+    /// Load a JSONL export (plain or gzipped) into the store
+    Import {
+        /// File written by `whisper export`
+        file: String,
+    },
+    
+    /// Show commit numbers per author and week, most-changed paths, type mix and active days
+    Stats {
+        /// Repository name, id or path (uses the active repository if omitted)
+        #[arg(short, long)]
+        repo: Option<String>,
+        
+        /// Print JSON instead of tables
+        #[arg(long)]
+        json: bool,
+        
+        /// How many of the most-changed paths to list
+        #[arg(long, default_value = "10")]
+        top: usize,
+        
+        #[command(flatten)]
+        filter: repository::CommitFilter,
+    },
+    
+    /// Fetch new commits for added repositories and store them
+    Sync {
+        /// Repository name, id or path (uses the active repository if omitted)
+        #[arg(short, long)]
+        repo: Option<String>,
+        
+        /// Sync every added repository
+        #[arg(long, conflicts_with = "repo")]
+        all: bool,
+        
+        /// Store the whole history again, not just commits since the last sync
+        #[arg(long)]
+        full: bool,
+    },
+    
+    /// List and manage added repositories
+    Repos {
+        #[command(subcommand)]
+        action: ReposAction,
+    },
+    
+    /// Browse and compare previously generated outputs
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    
+    /// Manage the project memory store
+    Store {
+        #[command(subcommand)]
+        action: StoreAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditAction {
+    /// List recorded model calls, newest first
+    List {
+        /// Maximum number of entries to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    
+    /// Show the exact prompt and response of one call
+    Show {
+        /// Entry id (or a unique prefix of it)
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReposAction {
+    /// List added repositories; the active one is marked
+    List,
+    
+    /// Make a repository the default for commands run without --repo
+    Use {
+        /// Repository name, id or path
+        repo: String,
+    },
+    
+    /// Unregister a repository (its files and stored history are kept)
+    Remove {
+        /// Repository name, id or path
+        repo: String,
+    },
+    
+    /// Give a repository a new name
+    Rename {
+        /// Repository name, id or path
+        repo: String,
+        
+        /// The new name
+        name: String,
+    },
+    
+    /// Show where a repository lives and what the store holds for it
+    Info {
+        /// Repository name, id or path (uses the active repository if omitted)
+        repo: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// List saved outputs, newest first
+    List {
+        /// Only outputs for this repository (name, id or path)
         #[arg(short, long)]
         repo: Option<String>,
+        
+        /// Only outputs of this command, e.g. "changelog"
+        #[arg(short, long)]
+        command: Option<String>,
+        
+        /// Maximum number of outputs to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    
+    /// Show one saved output
+    Show {
+        /// Output id (or a unique prefix of it)
+        id: String,
+    },
+    
+    /// Compare two saved outputs line by line
+    Diff {
+        /// The older output's id
+        old: String,
+        
+        /// The newer output's id
+        new: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum StoreAction {
+    /// Copy all repositories and commits from one backend to another
+    Migrate {
+        /// Backend to copy from
+        #[arg(long, value_enum, default_value = "mongodb")]
+        from: storage::Backend,
+        
+        /// Backend to copy into
+        #[arg(long, value_enum, default_value = "sqlite")]
+        to: storage::Backend,
     },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    
     match cli.command {
         Some(Commands::Setup) => {
             cli::setup::run_setup().await?;
         }
-        Some(Commands::Add { repo }) => {
-            cli::add::run_add(&repo).await?;
+        Some(Commands::Add { repo, clone }) => {
+            cli::add::run_add(&repo, &clone).await?;
         }
-        Some(Commands::Summary { repo }) => {
-            cli::summary::run_summary(repo.as_deref()).await?;
+        Some(Commands::Summary { repo, options }) => {
+            cli::summary::run_summary(repo.as_deref(), &options).await?;
         }
-        Some(Commands::Demo { repo }) => {
-            cli::demo::run_demo(repo.as_deref()).await?;
+        Some(Commands::Demo { repo, options }) => {
+            cli::demo::run_demo(repo.as_deref(), &options).await?;
         }
-        Some(Commands::Since { reference, repo }) => {
-            cli::since::run_since(&reference, repo.as_deref()).await?;
+        Some(Commands::Since { reference, repo, options }) => {
+            cli::since::run_since(&reference, repo.as_deref(), &options).await?;
         }
-        Some(Commands::Last { count, repo }) => {
-            cli::last::run_last(count, repo.as_deref()).await?;
+        Some(Commands::Last { count, repo, options }) => {
+            cli::last::run_last(count, repo.as_deref(), &options).await?;
         }
-        Some(Commands::Changelog { repo }) => {
-            cli::changelog::run_changelog(repo.as_deref()).await?;
+        Some(Commands::Changelog { repo, options }) => {
+            cli::changelog::run_changelog(repo.as_deref(), &options).await?;
         }
+        Some(Commands::Audit { action }) => match action {
+            AuditAction::List { limit } => cli::audit::run_audit_list(limit)?,
+            AuditAction::Show { id } => cli::audit::run_audit_show(&id)?,
+        },
+        Some(Commands::Export { repo, out, format, gzip }) => {
+            cli::export::run_export(repo.as_deref(), &out, format, gzip).await?;
+        }
+        Some(Commands::Import { file }) => {
+            cli::import::run_import(&file).await?;
+        }
+        Some(Commands::Stats { repo, json, top, filter }) => {
+            cli::stats::run_stats(repo.as_deref(), json, top, &filter).await?;
+        }
+        Some(Commands::Sync { repo, all, full }) => {
+            cli::sync::run_sync(repo.as_deref(), all, full).await?;
+        }
+        Some(Commands::Repos { action }) => match action {
+            ReposAction::List => cli::repos::run_repos_list()?,
+            ReposAction::Use { repo } => cli::repos::run_repos_use(&repo)?,
+            ReposAction::Remove { repo } => cli::repos::run_repos_remove(&repo)?,
+            ReposAction::Rename { repo, name } => cli::repos::run_repos_rename(&repo, &name).await?,
+            ReposAction::Info { repo } => cli::repos::run_repos_info(repo.as_deref()).await?,
+        },
+        Some(Commands::History { action }) => match action {
+            HistoryAction::List { repo, command, limit } => {
+                cli::history::run_history_list(repo.as_deref(), command.as_deref(), limit).await?
+            }
+            HistoryAction::Show { id } => cli::history::run_history_show(&id).await?,
+            HistoryAction::Diff { old, new } => cli::history::run_history_diff(&old, &new).await?,
+        },
+        Some(Commands::Store { action }) => match action {
+            StoreAction::Migrate { from, to } => cli::store::run_store_migrate(from, to).await?,
+        },
         None => {
             if let Some(path) = cli.repo_path {
                 // Quick analysis mode
-                cli::add::run_add(&path).await?;
-                cli::summary::run_summary(None).await?;
+                cli::add::run_add(&path, &cli.clone).await?;
+                cli::summary::run_summary(None, &cli.options).await?;
             } else {
                 cli::help::show_welcome();
             }
@@ -0,0 +1,267 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::*;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use crate::cli::GenerateOptions;
+use crate::config::Config;
+use crate::gemini::{self, estimate_tokens, GeminiClient, Narrative};
+use crate::guard;
+use crate::offline::OfflineNarrator;
+use crate::repository::Commit;
+use crate::storage::{self, Artifact, CommitQuery};
+use crate::workspace::{self, Workspace};
+
+/// Recorded as the model of outputs built by the offline heuristics.
+const OFFLINE_MODEL: &str = "offline";
+
+/// What the generating commands talk to: Gemini, or the offline heuristics.
+pub struct Narrator {
+    backend: Backend,
+    options: GenerateOptions,
+    command: String,
+    repo: String,
+}
+
+enum Backend {
+    Gemini(Box<GeminiClient>),
+    Offline(OfflineNarrator),
+}
+
+impl Narrator {
+    /// Builds the narrator for a command. Offline mode skips configuration
+    /// entirely, so it works without an API key or a setup run.
+    pub async fn new(command: &str, repo: &str, options: &GenerateOptions) -> Result<Self> {
+        let backend = if options.offline {
+            Backend::Offline(OfflineNarrator::new())
+        } else {
+            // A dry run never calls the API, so don't force the setup wizard for it.
+            let config = if options.dry_run {
+                Config::load_or_default()?
+            } else {
+                Config::load_or_setup().await?
+            };
+            let commit_links = Workspace::new()
+                .and_then(|workspace| workspace.find_by_path(repo))
+                .ok()
+                .flatten()
+                .and_then(|entry| entry.commit_url_prefix());
+            
+            let statistics = if options.with_stats {
+                load_statistics(repo, options).await
+            } else {
+                None
+            };
+            
+            let audience = options.audience
+                .map(|audience| audience.guidance(&config))
+                .transpose()?;
+            
+            Backend::Gemini(Box::new(
+                GeminiClient::from_config(&config)?
+                    .with_audit_context(command, repo)
+                    .with_commit_links(commit_links)
+                    .with_audience(audience)
+                    .with_language(options.lang.clone().or(Some(config.output_lang.clone())))
+                    .with_exploration(options.deep.then(|| repo.to_string()))
+                    .with_statistics(statistics),
+            ))
+        };
+        
+        Ok(Self {
+            backend,
+            options: options.clone(),
+            command: command.to_string(),
+            repo: repo.to_string(),
+        })
+    }
+    
+    /// Generates the narrative and keeps a copy in the store for `whisper history`.
+    pub async fn narrate(&self, narrative: &Narrative, commits: &[Commit]) -> Result<String> {
+        let (text, model) = self.generate(narrative, commits).await?;
+        
+        match self.save_artifact(narrative, commits, &text, &model).await {
+            Ok(id) => println!("{}", format!("🗂  Saved to history as {}", id).dimmed()),
+            Err(e) => println!("{}", format!("⚠️  Could not save this output to history: {}", e).yellow()),
+        }
+        
+        Ok(text)
+    }
+    
+    /// The narrative text and the model that wrote it (`offline` for the heuristics).
+    async fn generate(&self, narrative: &Narrative, commits: &[Commit]) -> Result<(String, String)> {
+        match &self.backend {
+            Backend::Offline(offline) => Ok((offline.narrate(narrative, commits), OFFLINE_MODEL.to_string())),
+            Backend::Gemini(client) => {
+                guard::print_warnings(&guard::scan_commits(commits));
+                
+                if self.options.show_prompt {
+                    print_prompt(client, narrative, commits);
+                }
+                
+                let result = client.narrate(narrative, commits).await;
+                
+                if self.options.show_redactions {
+                    client.redaction_report().print();
+                }
+                
+                let models = client.model_report();
+                for (model, reason) in &models.skipped {
+                    println!("{}", format!("⚠️  {} failed: {}", model, reason).yellow());
+                }
+                
+                match result {
+                    Ok(cited) => {
+                        if let Some(model) = &models.used {
+                            println!("{}", format!("🤖 Generated by {}", model).dimmed());
+                        }
+                        if !cited.unverified.is_empty() {
+                            println!(
+                                "{}",
+                                format!(
+                                    "⚠️  Removed {} citation(s) to commits that are not in the input: {}",
+                                    cited.unverified.len(),
+                                    cited.unverified.join(", ")
+                                ).yellow()
+                            );
+                        }
+                        Ok((cited.text, models.used.clone().unwrap_or_default()))
+                    }
+                    Err(e) if gemini::is_unreachable(&e) => {
+                        println!("{}", format!("⚠️  Gemini is unreachable ({}), falling back to offline mode", e).yellow());
+                        Ok((OfflineNarrator::new().narrate(narrative, commits), OFFLINE_MODEL.to_string()))
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+    
+    async fn save_artifact(&self, narrative: &Narrative, commits: &[Commit], text: &str, model: &str) -> Result<String> {
+        let repo_id = Workspace::new()?
+            .find_by_path(&self.repo)?
+            .map(|entry| entry.id)
+            .unwrap_or_else(|| workspace::repo_id(&self.repo, None));
+        
+        let now = chrono::Utc::now();
+        let hash = hex::encode(Sha256::digest(text.as_bytes()));
+        
+        let artifact = Artifact {
+            id: format!("{}-{}", now.format("%Y%m%d-%H%M%S"), &hash[..8]),
+            repo_id,
+            command: self.command.clone(),
+            parameters: self.parameters(narrative),
+            model: model.to_string(),
+            first_commit: commits.iter().min_by_key(|c| c.timestamp).map(|c| c.hash.clone()),
+            last_commit: commits.iter().max_by_key(|c| c.timestamp).map(|c| c.hash.clone()),
+            commit_count: commits.len() as i64,
+            output: text.to_string(),
+            timestamp: now.timestamp(),
+        };
+        
+        let store = storage::open(&Config::load_or_default()?).await?;
+        store.save_artifact(&artifact).await?;
+        
+        Ok(artifact.id)
+    }
+    
+    /// The flags that shaped this output, for comparing runs later.
+    fn parameters(&self, narrative: &Narrative) -> BTreeMap<String, String> {
+        let options = &self.options;
+        let filter = &options.filter;
+        let date = |timestamp: i64| {
+            chrono::DateTime::from_timestamp(timestamp, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        };
+        
+        let mut parameters = BTreeMap::new();
+        let mut set = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                parameters.insert(key.to_string(), value);
+            }
+        };
+        
+        if let Narrative::Since(reference) = narrative {
+            set("since", Some(reference.clone()));
+        }
+        set("audience", options.audience.and_then(|a| a.to_possible_value()).map(|v| v.get_name().to_string()));
+        set("lang", options.lang.clone());
+        set("deep", options.deep.then(|| "true".to_string()));
+        set("with_stats", options.with_stats.then(|| "true".to_string()));
+        set("author", filter.author.clone());
+        set("path", filter.path.clone());
+        set("kind", filter.kind.map(|kind| kind.name()));
+        set("after", filter.after.map(date));
+        set("before", filter.before.map(date));
+        
+        parameters
+    }
+    
+    /// Shows what `narrate` would send, without sending it.
+    pub fn dry_run(&self, narrative: &Narrative, commits: &[Commit]) -> Result<()> {
+        match &self.backend {
+            Backend::Offline(_) => {
+                println!("\n{}", "🧪 Dry run (offline): nothing would be sent to a model.".cyan().bold());
+                println!("{} {}", "Commits:".bold(), commits.len());
+            }
+            Backend::Gemini(client) => {
+                guard::print_warnings(&guard::scan_commits(commits));
+                print_prompt(client, narrative, commits);
+                println!("\n{}", "🧪 Dry run: the API was not called.".cyan().bold());
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// Store statistics for `repo` rendered for the prompt; a failure only warns,
+/// since the narrative still works without them.
+async fn load_statistics(repo: &str, options: &GenerateOptions) -> Option<String> {
+    let result = async {
+        let repo_id = Workspace::new()?
+            .find_by_path(repo)?
+            .map(|entry| entry.id)
+            .unwrap_or_else(|| workspace::repo_id(repo, None));
+        let store = storage::open(&Config::load_or_default()?).await?;
+        
+        store
+            .stats(
+                &CommitQuery {
+                    repo_id,
+                    filter: options.filter.clone(),
+                    limit: None,
+                },
+                10,
+            )
+            .await
+    }
+    .await;
+    
+    match result {
+        Ok(stats) if stats.commits > 0 => Some(crate::cli::stats::describe(&stats)),
+        Ok(_) => {
+            println!("{}", "⚠️  No stored commits to compute statistics from; continuing without them".yellow());
+            None
+        }
+        Err(e) => {
+            println!("{}", format!("⚠️  Could not load statistics, continuing without them ({})", e).yellow());
+            None
+        }
+    }
+}
+
+fn print_prompt(client: &GeminiClient, narrative: &Narrative, commits: &[Commit]) {
+    let prompt = client.render_prompt(narrative, commits);
+    let tokens = estimate_tokens(client.system_instruction()) + estimate_tokens(&prompt);
+    
+    println!("\n{}", "─".repeat(60).dimmed());
+    println!("{}", "SYSTEM INSTRUCTION".dimmed().bold());
+    println!("{}", client.system_instruction().dimmed());
+    println!("\n{}", "PROMPT".dimmed().bold());
+    println!("{}", prompt);
+    println!("{}", "─".repeat(60).dimmed());
+    println!("{} {}", "Commits:".bold(), commits.len());
+    println!("{} ~{}", "Estimated tokens:".bold(), tokens);
+}
@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{TimeZone, Utc};
+use crate::gemini::Narrative;
+use crate::repository::{Commit, CommitKind};
+
+/// Deterministic, template-based narratives built from commit data alone.
+///
+/// Used for `--offline` and as the fallback when Gemini is unreachable.
+#[derive(Default)]
+pub struct OfflineNarrator;
+
+struct Stats<'a> {
+    by_kind: BTreeMap<CommitKind, Vec<&'a Commit>>,
+    authors: Vec<(String, usize)>,
+    areas: Vec<(String, usize)>,
+    first: i64,
+    last: i64,
+}
+
+impl OfflineNarrator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn narrate(&self, narrative: &Narrative, commits: &[Commit]) -> String {
+        if commits.is_empty() {
+            return format!("No commits provided to generate a {}.", narrative.noun());
+        }
+
+        let stats = Stats::collect(commits);
+
+        match narrative {
+            Narrative::Summary => format!(
+                "{}\n\n{}\n\n{}",
+                self.paragraph(commits, &stats),
+                self.areas(&stats),
+                self.contributors(&stats)
+            ),
+            Narrative::Changelog => self.changelog(&stats),
+            Narrative::Demo => self.demo(commits, &stats),
+            Narrative::Recent => format!(
+                "{}\n\n{}",
+                self.paragraph(commits, &stats),
+                self.changelog(&stats)
+            ),
+            Narrative::Since(reference) => format!(
+                "Since '{}' there have been {} commits.\n\n{}\n\n{}",
+                reference,
+                commits.len(),
+                self.changelog(&stats),
+                self.contributors(&stats)
+            ),
+        }
+    }
+
+    fn paragraph(&self, commits: &[Commit], stats: &Stats) -> String {
+        let mix = stats.by_kind
+            .iter()
+            .map(|(kind, list)| format!("{}: {}", kind.label().to_lowercase(), list.len()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let focus = stats.areas
+            .iter()
+            .take(3)
+            .map(|(area, _)| format!("`{}`", area))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut text = format!(
+            "Between {} and {}, {} contributor{} made {} commit{} ({}).",
+            format_date(stats.first),
+            format_date(stats.last),
+            stats.authors.len(),
+            plural(stats.authors.len()),
+            commits.len(),
+            plural(commits.len()),
+            mix
+        );
+
+        if !focus.is_empty() {
+            text.push_str(&format!(" Most of the activity touched {}.", focus));
+        }
+
+        if let Some(latest) = commits.iter().rev().max_by_key(|c| c.timestamp) {
+            text.push_str(&format!(" The latest change was \"{}\".", latest.subject()));
+        }
+
+        text
+    }
+
+    fn changelog(&self, stats: &Stats) -> String {
+        stats.by_kind
+            .iter()
+            .map(|(kind, list)| {
+                let entries = list
+                    .iter()
+                    .map(|c| format!("- {} ({})", c.subject(), c.short_hash()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("### {}\n{}", kind.label(), entries)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn areas(&self, stats: &Stats) -> String {
+        let lines = stats.areas
+            .iter()
+            .take(5)
+            .map(|(area, count)| format!("- {} ({} file change{})", area, count, plural(*count)))
+            .collect::<Vec<_>>();
+
+        if lines.is_empty() {
+            return "Top-changed areas: none recorded.".to_string();
+        }
+
+        format!("Top-changed areas:\n{}", lines.join("\n"))
+    }
+
+    fn contributors(&self, stats: &Stats) -> String {
+        let lines = stats.authors
+            .iter()
+            .map(|(author, count)| format!("- {} ({} commit{})", author, count, plural(*count)))
+            .collect::<Vec<_>>();
+
+        format!("Contributors:\n{}", lines.join("\n"))
+    }
+
+    fn demo(&self, commits: &[Commit], stats: &Stats) -> String {
+        let highlights = stats.by_kind
+            .get(&CommitKind::Feature)
+            .into_iter()
+            .flatten()
+            .chain(stats.by_kind.get(&CommitKind::Fix).into_iter().flatten())
+            .take(3)
+            .map(|c| format!("- {}", c.subject()))
+            .collect::<Vec<_>>();
+
+        let highlights = if highlights.is_empty() {
+            commits.iter().take(3).map(|c| format!("- {}", c.subject())).collect()
+        } else {
+            highlights
+        };
+
+        let focus = stats.areas
+            .iter()
+            .take(2)
+            .map(|(area, _)| area.as_str())
+            .collect::<Vec<_>>()
+            .join(" and ");
+
+        format!(
+            "[Intro] Here's what we've been building lately: {} commit{}{}.\n\n\
+             [Highlights]\n{}\n\n\
+             [Wrap-up] {} contributor{} shipped this, and the work is concentrated where it matters most.",
+            commits.len(),
+            plural(commits.len()),
+            if focus.is_empty() { String::new() } else { format!(" across {}", focus) },
+            highlights.join("\n"),
+            stats.authors.len(),
+            plural(stats.authors.len())
+        )
+    }
+}
+
+impl<'a> Stats<'a> {
+    fn collect(commits: &'a [Commit]) -> Self {
+        let mut by_kind: BTreeMap<CommitKind, Vec<&Commit>> = BTreeMap::new();
+        let mut authors: HashMap<String, usize> = HashMap::new();
+        let mut areas: HashMap<String, usize> = HashMap::new();
+
+        for commit in commits {
+            by_kind.entry(commit.kind()).or_default().push(commit);
+            *authors.entry(commit.author.clone()).or_default() += 1;
+            for file in &commit.files_changed {
+                *areas.entry(area_of(&file.file_path)).or_default() += 1;
+            }
+        }
+
+        Self {
+            by_kind,
+            authors: sorted_counts(authors),
+            areas: sorted_counts(areas),
+            first: commits.iter().map(|c| c.timestamp).min().unwrap_or_default(),
+            last: commits.iter().map(|c| c.timestamp).max().unwrap_or_default(),
+        }
+    }
+}
+
+/// Groups a path by its top two directories, e.g. `src/cli/add.rs` -> `src/cli`.
+fn area_of(path: &str) -> String {
+    let parts: Vec<&str> = path.split('/').collect();
+    match parts.len() {
+        1 => "(root)".to_string(),
+        2 => parts[0].to_string(),
+        _ => format!("{}/{}", parts[0], parts[1]),
+    }
+}
+
+/// Sorts by count descending, then name, so output is stable between runs.
+fn sorted_counts(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut sorted: Vec<_> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted
+}
+
+fn format_date(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "an unknown date".to_string())
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::FileChange;
+
+    fn commit(hash: &str, message: &str, author: &str, timestamp: i64, files: &[&str]) -> Commit {
+        Commit {
+            hash: hash.to_string(),
+            message: message.to_string(),
+            author: author.to_string(),
+            timestamp,
+            files_changed: files
+                .iter()
+                .map(|path| FileChange { file_path: path.to_string(), ..Default::default() })
+                .collect(),
+        }
+    }
+
+    // Newest first, as commits are loaded
+    fn commits() -> Vec<Commit> {
+        vec![
+            commit("c3c3c3c3c3", "fix: crash on empty password", "Grace Hopper", 1704153600, &["src/auth/login.rs"]),
+            commit("b2b2b2b2b2", "docs: explain setup", "Ada Lovelace", 1704110400, &["README.md"]),
+            commit("a1a1a1a1a1", "feat: login form", "Ada Lovelace", 1704067200, &["src/auth/login.rs", "src/main.rs"]),
+        ]
+    }
+
+    #[test]
+    fn changelog_groups_commits_by_kind() {
+        let changelog = OfflineNarrator::new().narrate(&Narrative::Changelog, &commits());
+
+        assert_eq!(
+            changelog,
+            "### Features\n- feat: login form (a1a1a1a)\n\n\
+             ### Fixes\n- fix: crash on empty password (c3c3c3c)\n\n\
+             ### Documentation\n- docs: explain setup (b2b2b2b)"
+        );
+    }
+
+    #[test]
+    fn summary_describes_span_areas_and_contributors() {
+        let summary = OfflineNarrator::new().narrate(&Narrative::Summary, &commits());
+
+        assert_eq!(
+            summary,
+            "Between 2024-01-01 and 2024-01-02, 2 contributors made 3 commits (features: 1, fixes: 1, documentation: 1). \
+             Most of the activity touched `src/auth`, `(root)`, `src`. The latest change was \"fix: crash on empty password\".\n\n\
+             Top-changed areas:\n- src/auth (2 file changes)\n- (root) (1 file change)\n- src (1 file change)\n\n\
+             Contributors:\n- Ada Lovelace (2 commits)\n- Grace Hopper (1 commit)"
+        );
+    }
+
+    #[test]
+    fn same_commits_give_the_same_narrative() {
+        let narrator = OfflineNarrator::new();
+        for narrative in [Narrative::Summary, Narrative::Demo, Narrative::Recent, Narrative::Since("v1.0".to_string())] {
+            assert_eq!(narrator.narrate(&narrative, &commits()), narrator.narrate(&narrative, &commits()));
+        }
+    }
+
+    #[test]
+    fn no_commits_says_so() {
+        assert_eq!(
+            OfflineNarrator::new().narrate(&Narrative::Demo, &[]),
+            "No commits provided to generate a demo script."
+        );
+    }
+}
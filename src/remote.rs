@@ -0,0 +1,400 @@
+use anyhow::{Result, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{Cred, CredentialType, ErrorCode, FetchOptions, RemoteCallbacks, Repository};
+use regex::Regex;
+use std::cell::RefCell;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use crate::config::Config;
+
+/// Returned from the credentials callback once every option has been offered.
+const NO_MORE_CREDENTIALS: &str = "no more credentials to try";
+
+/// Keys tried after ssh-agent and `GIT_SSH_KEY`, in `~/.ssh`.
+const DEFAULT_SSH_KEYS: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// How much of a remote to download when cloning.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct CloneOptions {
+    /// Only download the last N commits (shallow clone)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub depth: Option<u32>,
+    
+    /// Only download commits made after this date (YYYY-MM-DD; needs the git CLI)
+    #[arg(long, value_name = "DATE", value_parser = crate::cli::parse_date)]
+    pub since: Option<i64>,
+    
+    /// Skip file contents, which whisper doesn't read (needs the git CLI)
+    #[arg(long)]
+    pub blobless: bool,
+}
+
+impl CloneOptions {
+    /// libgit2 can't do date cutoffs or object filters.
+    pub fn needs_git_cli(&self) -> bool {
+        self.since.is_some() || self.blobless
+    }
+}
+
+/// A snapshot of a clone or fetch in progress.
+pub struct Transfer {
+    pub stage: &'static str,
+    pub done: usize,
+    pub total: usize,
+    /// Bytes received so far, when known.
+    pub bytes: Option<usize>,
+}
+
+/// What fetching an existing clone did to its checked-out branch.
+pub enum Update {
+    UpToDate,
+    FastForwarded { from: String, to: String },
+    /// The remote branch was rewritten (e.g. force-pushed), so it can't be fast-forwarded.
+    Diverged,
+}
+
+/// Clones `url` into `dir`, reporting progress through `on_progress`.
+pub fn clone(
+    url: &str,
+    dir: &Path,
+    config: &Config,
+    options: &CloneOptions,
+    on_progress: &dyn Fn(Transfer),
+) -> Result<()> {
+    // libgit2's shallow negotiation only works over HTTP(S); upload-pack
+    // rejects its requests on the git and SSH protocols
+    let http = url.starts_with("http://") || url.starts_with("https://");
+    if options.needs_git_cli() || options.depth.is_some() && !http {
+        return clone_with_git_cli(url, dir, config, options, on_progress);
+    }
+    
+    let tried = Rc::new(RefCell::new(Vec::new()));
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks(config, tried.clone(), on_progress));
+    if let Some(depth) = options.depth {
+        fetch_options.depth(depth as i32);
+    }
+    
+    RepoBuilder::new()
+        .fetch_options(fetch_options)
+        .clone(url, dir)
+        .map_err(|e| explain(e, url, &tried.borrow()))
+        .with_context(|| format!("Failed to clone {}", url))?;
+    
+    Ok(())
+}
+
+/// Fetches `origin` into the clone at `dir` and fast-forwards the checked-out
+/// branch (the remote's default branch, for clones we made) to it.
+pub fn update(dir: &Path, config: &Config, on_progress: &dyn Fn(Transfer)) -> Result<Update> {
+    let repo = Repository::open(dir).with_context(|| format!("{} is not a git repository", dir.display()))?;
+    
+    let head = repo.head().context("The clone has no HEAD")?;
+    if !head.is_branch() {
+        anyhow::bail!("{} has a detached HEAD; check out a branch first", dir.display());
+    }
+    let branch_ref = head.name().context("Branch name is not valid UTF-8")?.to_string();
+    let branch = head.shorthand().unwrap_or_default().to_string();
+    let from = head.target().context("HEAD points nowhere")?;
+    
+    let mut remote = repo.find_remote("origin").context("The clone has no 'origin' remote")?;
+    let url = remote.url().unwrap_or("origin").to_string();
+    let tried = Rc::new(RefCell::new(Vec::new()));
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks(config, tried.clone(), on_progress));
+    
+    remote
+        .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+        .map_err(|e| explain(e, &url, &tried.borrow()))
+        .with_context(|| format!("Failed to fetch {}", url))?;
+    
+    let upstream = repo
+        .find_reference(&format!("refs/remotes/origin/{}", branch))
+        .with_context(|| format!("origin has no branch '{}'", branch))?;
+    let target = repo.reference_to_annotated_commit(&upstream)?;
+    let (analysis, _) = repo.merge_analysis(&[&target])?;
+    
+    if analysis.is_up_to_date() {
+        return Ok(Update::UpToDate);
+    }
+    if !analysis.is_fast_forward() {
+        return Ok(Update::Diverged);
+    }
+    
+    repo.find_reference(&branch_ref)?
+        .set_target(target.id(), "whisper: fast-forward")?;
+    repo.set_head(&branch_ref)?;
+    repo.checkout_head(Some(CheckoutBuilder::default().force()))?;
+    
+    Ok(Update::FastForwarded {
+        from: from.to_string(),
+        to: target.id().to_string(),
+    })
+}
+
+/// Shallow-since and blobless clones through `git clone`, with its progress
+/// output parsed into [`Transfer`]s. Credentials come from git's own setup,
+/// plus `GIT_TOKEN` (for its host) and `GIT_SSH_KEY` passed through the environment.
+fn clone_with_git_cli(
+    url: &str,
+    dir: &Path,
+    config: &Config,
+    options: &CloneOptions,
+    on_progress: &dyn Fn(Transfer),
+) -> Result<()> {
+    let mut command = Command::new("git");
+    command.args(["clone", "--progress"]);
+    
+    if let Some(depth) = options.depth {
+        command.arg(format!("--depth={}", depth));
+    }
+    if let Some(since) = options.since {
+        let date = chrono::DateTime::from_timestamp(since, 0).unwrap_or_default();
+        command.arg(format!("--shallow-since={}", date.format("%Y-%m-%d")));
+    }
+    if options.blobless {
+        command.arg("--filter=blob:none");
+    }
+    command.arg(url).arg(dir);
+    
+    // Never stop to ask for a password; fail with git's message instead
+    command.env("GIT_TERMINAL_PROMPT", "0");
+    if let Some(token) = token_for(config, url) {
+        // Config from the environment keeps the token out of the process list,
+        // and the URL-scoped key keeps it off redirects to other hosts
+        let basic = BASE64.encode(format!("{}:{}", config.git_username, token));
+        command
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", format!("http.https://{}/.extraHeader", config.git_token_host.trim()))
+            .env("GIT_CONFIG_VALUE_0", format!("Authorization: Basic {}", basic));
+    }
+    if !config.git_ssh_key.is_empty() {
+        command.env("GIT_SSH_COMMAND", format!("ssh -i '{}'", config.git_ssh_key.replace('\'', "'\\''")));
+    }
+    
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git; --since and --blobless need the git command line tool")?;
+    
+    let progress = Regex::new(r"^(?:remote: )?([A-Z][a-z]+ [a-z]+):\s+\d+% \((\d+)/(\d+)\)").unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    let mut output = String::new();
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    
+    // Progress lines end in '\r' while they update, '\n' once done
+    while stderr.read(&mut byte)? == 1 {
+        if byte[0] != b'\r' && byte[0] != b'\n' {
+            line.push(byte[0]);
+            continue;
+        }
+        
+        let text = String::from_utf8_lossy(&line).to_string();
+        if let Some(captures) = progress.captures(&text) {
+            let stage = match &captures[1] {
+                "Receiving objects" => "Receiving objects",
+                "Resolving deltas" => "Resolving deltas",
+                "Counting objects" => "Counting objects",
+                "Compressing objects" => "Compressing objects",
+                _ => "Transferring",
+            };
+            on_progress(Transfer {
+                stage,
+                done: captures[2].parse().unwrap_or(0),
+                total: captures[3].parse().unwrap_or(0),
+                bytes: None,
+            });
+        } else if byte[0] == b'\n' && !text.trim().is_empty() && !text.starts_with("Cloning into") {
+            output.push_str(&text);
+            output.push('\n');
+        }
+        line.clear();
+    }
+    
+    if !child.wait()?.success() {
+        anyhow::bail!("git clone {} failed: {}", url, output.trim());
+    }
+    
+    Ok(())
+}
+
+/// `GIT_TOKEN`, if `url` is an HTTPS URL on `GIT_TOKEN_HOST`. The token is
+/// never offered to other hosts or over plain HTTP.
+fn token_for<'a>(config: &'a Config, url: &str) -> Option<&'a str> {
+    let rest = url.strip_prefix("https://")?;
+    let authority = rest.split('/').next()?;
+    let host = authority.rsplit('@').next()?;
+    
+    let matches = !config.git_token.is_empty()
+        && !config.git_token_host.trim().is_empty()
+        && host.eq_ignore_ascii_case(config.git_token_host.trim());
+    matches.then_some(config.git_token.as_str())
+}
+
+/// Answers libgit2's credential requests and forwards transfer progress.
+/// Each credential call offers the next untried option (ssh-agent,
+/// `GIT_SSH_KEY`, default keys for SSH; `GIT_TOKEN` on its own host, then
+/// git's credential helpers for HTTPS) and records it in `tried`.
+fn callbacks<'a>(
+    config: &'a Config,
+    tried: Rc<RefCell<Vec<String>>>,
+    on_progress: &'a dyn Fn(Transfer),
+) -> RemoteCallbacks<'a> {
+    let mut ssh_keys = ssh_keys(config).into_iter();
+    let mut offered_agent = false;
+    let mut offered_token = false;
+    let mut offered_helper = false;
+    
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        let user = username.unwrap_or("git");
+        
+        if allowed.contains(CredentialType::SSH_KEY) {
+            // Without a running agent libgit2 errors out instead of asking again
+            if !offered_agent && std::env::var_os("SSH_AUTH_SOCK").is_some() {
+                offered_agent = true;
+                tried.borrow_mut().push("ssh-agent".to_string());
+                return Cred::ssh_key_from_agent(user);
+            }
+            if let Some(key) = ssh_keys.next() {
+                tried.borrow_mut().push(key.display().to_string());
+                let passphrase = (key == Path::new(&config.git_ssh_key) && !config.git_ssh_key_passphrase.is_empty())
+                    .then_some(config.git_ssh_key_passphrase.as_str());
+                return Cred::ssh_key(user, None, &key, passphrase);
+            }
+        } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(token) = token_for(config, url).filter(|_| !offered_token) {
+                offered_token = true;
+                tried.borrow_mut().push("GIT_TOKEN".to_string());
+                let user = username.unwrap_or(&config.git_username);
+                return Cred::userpass_plaintext(user, token);
+            }
+            if !offered_helper {
+                offered_helper = true;
+                tried.borrow_mut().push("git credential helper".to_string());
+                if let Ok(cred) = git2::Config::open_default().and_then(|c| Cred::credential_helper(&c, url, username)) {
+                    return Ok(cred);
+                }
+            }
+        } else if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(user);
+        } else if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        
+        Err(git2::Error::from_str(NO_MORE_CREDENTIALS))
+    });
+    
+    callbacks.transfer_progress(move |progress| {
+        let transfer = if progress.received_objects() < progress.total_objects() {
+            Transfer {
+                stage: "Receiving objects",
+                done: progress.received_objects(),
+                total: progress.total_objects(),
+                bytes: Some(progress.received_bytes()),
+            }
+        } else if progress.total_deltas() > 0 {
+            Transfer {
+                stage: "Resolving deltas",
+                done: progress.indexed_deltas(),
+                total: progress.total_deltas(),
+                bytes: Some(progress.received_bytes()),
+            }
+        } else {
+            Transfer {
+                stage: "Indexing objects",
+                done: progress.indexed_objects(),
+                total: progress.total_objects(),
+                bytes: Some(progress.received_bytes()),
+            }
+        };
+        on_progress(transfer);
+        true
+    });
+    
+    callbacks
+}
+
+/// `GIT_SSH_KEY` first, then the usual keys in `~/.ssh` that exist.
+fn ssh_keys(config: &Config) -> Vec<PathBuf> {
+    let mut keys = Vec::new();
+    
+    if !config.git_ssh_key.is_empty() {
+        keys.push(PathBuf::from(&config.git_ssh_key));
+    }
+    
+    if let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) {
+        for key in DEFAULT_SSH_KEYS.iter().map(|name| ssh_dir.join(name)) {
+            if key.is_file() && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    
+    keys
+}
+
+/// Turns an authentication failure into an error that says what was tried
+/// and how to fix it; other errors pass through.
+fn explain(error: git2::Error, url: &str, tried: &[String]) -> anyhow::Error {
+    let auth_failed = error.code() == ErrorCode::Auth
+        || error.message().contains(NO_MORE_CREDENTIALS)
+        || error.message().contains("authentication");
+    
+    if !auth_failed {
+        return error.into();
+    }
+    
+    let tried = if tried.is_empty() {
+        "no credentials were available".to_string()
+    } else {
+        format!("tried {}", tried.join(", "))
+    };
+    let hint = if url.starts_with("http://") || url.starts_with("https://") {
+        "Set GIT_TOKEN to a personal access token and GIT_TOKEN_HOST to this host \
+         (and GIT_USERNAME if it needs one), or configure a git credential helper"
+    } else {
+        "Add your key to ssh-agent (ssh-add), or set GIT_SSH_KEY to a private key file \
+         (and GIT_SSH_KEY_PASSPHRASE if it has one)"
+    };
+    
+    anyhow::anyhow!("Authentication failed for {} ({}). {}", url, tried, hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config(token: &str, host: &str) -> Config {
+        Config {
+            git_token: token.to_string(),
+            git_token_host: host.to_string(),
+            ..Config::default()
+        }
+    }
+    
+    #[test]
+    fn token_goes_only_to_its_host_over_https() {
+        let config = config("secret", "github.com");
+        
+        assert_eq!(token_for(&config, "https://github.com/acme/api"), Some("secret"));
+        assert_eq!(token_for(&config, "https://me@GitHub.com/acme/api.git"), Some("secret"));
+        assert_eq!(token_for(&config, "http://github.com/acme/api"), None);
+        assert_eq!(token_for(&config, "https://evil.example/acme/api"), None);
+        assert_eq!(token_for(&config, "https://github.com.evil.example/acme/api"), None);
+        assert_eq!(token_for(&config, "https://github.com@evil.example/acme/api"), None);
+        assert_eq!(token_for(&config, "git@github.com:acme/api.git"), None);
+    }
+    
+    #[test]
+    fn no_token_without_a_host() {
+        assert_eq!(token_for(&config("secret", ""), "https://github.com/acme/api"), None);
+        assert_eq!(token_for(&config("", "github.com"), "https://github.com/acme/api"), None);
+    }
+}
@@ -3,7 +3,9 @@ use git2::Repository;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Stored copies may predate newer fields, so missing fields deserialize to defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Commit {
     pub hash: String,
     pub message: String,
@@ -12,12 +14,160 @@ pub struct Commit {
     pub files_changed: Vec<FileChange>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FileChange {
     pub file_path: String,
     pub summary: String,
 }
 
+/// Rough category of a commit, taken from its conventional-commit prefix
+/// or, failing that, from keywords in the subject line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitKind {
+    Feature,
+    Fix,
+    Refactor,
+    Performance,
+    Docs,
+    Test,
+    Chore,
+    #[default]
+    Other,
+}
+
+/// Narrows down which commits of a repository a command works on.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct CommitFilter {
+    /// Only include commits by authors whose name contains this text
+    #[arg(long)]
+    pub author: Option<String>,
+    
+    /// Only include commits touching files under this path
+    #[arg(long)]
+    pub path: Option<String>,
+    
+    /// Only include commits of this type
+    #[arg(long, value_enum)]
+    pub kind: Option<CommitKind>,
+    
+    /// Only include commits made on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = crate::cli::parse_date)]
+    pub after: Option<i64>,
+    
+    /// Only include commits made before this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = crate::cli::parse_date)]
+    pub before: Option<i64>,
+}
+
+impl CommitKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Feature => "Features",
+            Self::Fix => "Fixes",
+            Self::Refactor => "Refactors",
+            Self::Performance => "Performance",
+            Self::Docs => "Documentation",
+            Self::Test => "Tests",
+            Self::Chore => "Chores",
+            Self::Other => "Other changes",
+        }
+    }
+    
+    /// Lowercase identifier, as accepted by `--kind` and kept in the store.
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+    
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "feat" | "feature" => Some(Self::Feature),
+            "fix" | "bugfix" | "hotfix" => Some(Self::Fix),
+            "refactor" | "style" => Some(Self::Refactor),
+            "perf" => Some(Self::Performance),
+            "docs" | "doc" => Some(Self::Docs),
+            "test" | "tests" => Some(Self::Test),
+            "chore" | "build" | "ci" | "revert" => Some(Self::Chore),
+            _ => None,
+        }
+    }
+    
+    fn from_keywords(subject: &str) -> Self {
+        const RULES: &[(&[&str], CommitKind)] = &[
+            (&["fix", "bug", "crash", "error", "issue", "resolve", "patch"], CommitKind::Fix),
+            (&["add", "implement", "introduce", "support", "new", "create"], CommitKind::Feature),
+            (&["refactor", "clean", "restructure", "rename", "simplify", "move"], CommitKind::Refactor),
+            (&["perf", "speed", "faster", "optimi", "cache"], CommitKind::Performance),
+            (&["doc", "readme", "comment", "guide"], CommitKind::Docs),
+            (&["test", "spec", "coverage"], CommitKind::Test),
+            (&["bump", "release", "version", "deps", "dependency", "ci", "merge"], CommitKind::Chore),
+        ];
+        
+        let subject = subject.to_lowercase();
+        let words: Vec<&str> = subject
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        
+        RULES
+            .iter()
+            .find(|(keywords, _)| {
+                words.iter().any(|word| keywords.iter().any(|k| word.starts_with(k)))
+            })
+            .map(|(_, kind)| *kind)
+            .unwrap_or(Self::Other)
+    }
+}
+
+impl Commit {
+    /// First line of the commit message.
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or("").trim()
+    }
+    
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+    
+    pub fn kind(&self) -> CommitKind {
+        let subject = self.subject();
+        
+        // Conventional commits: "feat: ...", "fix(parser): ...", "refactor!: ..."
+        if let Some((prefix, _)) = subject.split_once(':') {
+            let prefix = prefix
+                .split('(')
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('!')
+                .trim()
+                .to_lowercase();
+            if let Some(kind) = CommitKind::from_prefix(&prefix) {
+                return kind;
+            }
+        }
+        
+        CommitKind::from_keywords(subject)
+    }
+}
+
+impl CommitFilter {
+    pub fn matches(&self, commit: &Commit) -> bool {
+        self.after.is_none_or(|after| commit.timestamp >= after)
+            && self.before.is_none_or(|before| commit.timestamp < before)
+            && self.author.as_ref().is_none_or(|author| {
+                commit.author.to_lowercase().contains(&author.to_lowercase())
+            })
+            && self.path.as_ref().is_none_or(|path| {
+                commit.files_changed.iter().any(|f| f.file_path.starts_with(path.as_str()))
+            })
+            && self.kind.is_none_or(|kind| commit.kind() == kind)
+    }
+}
+
 pub struct GitAnalyzer {
     repo: Repository,
 }
@@ -31,10 +181,22 @@ impl GitAnalyzer {
     }
     
     pub fn get_commits(&self) -> Result<Vec<Commit>> {
+        self.get_commits_after(None)
+    }
+    
+    /// Commits reachable from HEAD but not from `known`, e.g. the head stored
+    /// at the last sync. An unknown `known` (rewritten history) yields every commit.
+    pub fn get_commits_after(&self, known: Option<&str>) -> Result<Vec<Commit>> {
         let mut revwalk = self.repo.revwalk()?;
         revwalk.push_head()?;
         revwalk.set_sorting(git2::Sort::TIME)?;
         
+        if let Some(oid) = known.and_then(|hash| git2::Oid::from_str(hash).ok()) {
+            if self.repo.find_commit(oid).is_ok() {
+                revwalk.hide(oid)?;
+            }
+        }
+        
         let mut commits = Vec::new();
         
         for oid in revwalk {
@@ -61,6 +223,110 @@ impl GitAnalyzer {
         Ok(commits)
     }
     
+    /// Whether `hash` names a commit reachable from HEAD, e.g. the head
+    /// stored at the last sync before the history was rewritten.
+    pub fn contains(&self, hash: &str) -> bool {
+        let (Ok(oid), Ok(head)) = (git2::Oid::from_str(hash), self.repo.head().and_then(|h| h.peel_to_commit())) else {
+            return false;
+        };
+        head.id() == oid || self.repo.graph_descendant_of(head.id(), oid).unwrap_or(false)
+    }
+    
+    /// Full hash of the commit HEAD points at.
+    pub fn head(&self) -> Result<String> {
+        Ok(self.repo.head()?.peel_to_commit()?.id().to_string())
+    }
+    
+    /// Message, metadata and patch of the commit `rev` resolves to.
+    pub fn show_commit(&self, rev: &str, max_chars: usize) -> Result<String> {
+        let commit = self.resolve_commit(rev)?;
+        let tree = commit.tree()?;
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0)?.tree()?)
+        } else {
+            None
+        };
+        
+        let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+        
+        let mut output = format!(
+            "commit {}\nAuthor: {}\nDate: {}\n\n{}\n\n",
+            commit.id(),
+            commit.author().name().unwrap_or("Unknown"),
+            commit.time().seconds(),
+            commit.message().unwrap_or("").trim()
+        );
+        
+        diff.print(git2::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                output.push(line.origin());
+            }
+            output.push_str(&String::from_utf8_lossy(line.content()));
+            output.len() < max_chars
+        }).ok();
+        
+        Ok(truncate(output, max_chars))
+    }
+    
+    /// Paths of all files at `rev`, optionally limited to those under `prefix`.
+    pub fn list_files(&self, rev: &str, prefix: Option<&str>, limit: usize) -> Result<Vec<String>> {
+        let tree = self.resolve_commit(rev)?.tree()?;
+        let mut files = Vec::new();
+        
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                let path = format!("{}{}", dir, entry.name().unwrap_or(""));
+                if prefix.is_none_or(|p| path.starts_with(p)) {
+                    files.push(path);
+                }
+            }
+            if files.len() >= limit {
+                git2::TreeWalkResult::Abort
+            } else {
+                git2::TreeWalkResult::Ok
+            }
+        }).ok();
+        
+        Ok(files)
+    }
+    
+    /// Contents of `path` at `rev`, as text.
+    pub fn read_file(&self, rev: &str, path: &str, max_chars: usize) -> Result<String> {
+        let tree = self.resolve_commit(rev)?.tree()?;
+        let entry = tree.get_path(Path::new(path))
+            .with_context(|| format!("'{}' does not exist at {}", path, rev))?;
+        let blob = entry.to_object(&self.repo)?
+            .into_blob()
+            .map_err(|_| anyhow::anyhow!("'{}' is a directory at {}", path, rev))?;
+        
+        if blob.is_binary() {
+            return Ok(format!("(binary file, {} bytes)", blob.size()));
+        }
+        
+        Ok(truncate(String::from_utf8_lossy(blob.content()).to_string(), max_chars))
+    }
+    
+    /// Commits whose message or changed paths mention `query`, newest first.
+    pub fn search_commits(&self, query: &str, limit: usize) -> Result<Vec<Commit>> {
+        let query = query.to_lowercase();
+        
+        Ok(self.get_commits()?
+            .into_iter()
+            .filter(|c| {
+                c.message.to_lowercase().contains(&query)
+                    || c.files_changed.iter().any(|f| f.file_path.to_lowercase().contains(&query))
+            })
+            .take(limit)
+            .collect())
+    }
+    
+    fn resolve_commit(&self, rev: &str) -> Result<git2::Commit<'_>> {
+        self.repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("Unknown revision '{}'", rev))
+    }
+    
     fn get_file_changes(&self, commit: &git2::Commit) -> Result<Vec<FileChange>> {
         let mut files = Vec::new();
         
@@ -77,24 +343,25 @@ impl GitAnalyzer {
             None,
         )?;
         
-        diff.foreach(
-            &mut |delta, _| {
-                if let Some(path) = delta.new_file().path() {
-                    let file_path = path.to_string_lossy().to_string();
-                    let summary = format!("{:?}", delta.status());
-                    
-                    files.push(FileChange {
-                        file_path,
-                        summary,
-                    });
-                }
-                true
-            },
-            None,
-            None,
-            None,
-        )?;
+        // Deltas come from the trees alone; `foreach` would read every blob's
+        // header, which a blobless clone doesn't have
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path() {
+                files.push(FileChange {
+                    file_path: path.to_string_lossy().to_string(),
+                    summary: format!("{:?}", delta.status()),
+                });
+            }
+        }
         
         Ok(files)
     }
 }
+
+fn truncate(mut text: String, max_chars: usize) -> String {
+    if let Some((index, _)) = text.char_indices().nth(max_chars) {
+        text.truncate(index);
+        text.push_str("\n… (truncated)");
+    }
+    text
+}
@@ -0,0 +1,171 @@
+mod mongo;
+mod sqlite;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::config::Config;
+use crate::repository::{Commit, CommitFilter};
+use crate::workspace::RepoEntry;
+
+pub use mongo::MongoStore;
+pub use sqlite::SqliteStore;
+
+/// Which store holds the project memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// Embedded database file, no server needed (default).
+    Sqlite,
+    /// Shared MongoDB deployment, e.g. for a team.
+    Mongodb,
+}
+
+impl Backend {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "sqlite" => Ok(Self::Sqlite),
+            "mongodb" | "mongo" => Ok(Self::Mongodb),
+            other => anyhow::bail!("Unknown STORE_BACKEND '{}' (expected sqlite or mongodb)", other),
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sqlite => "sqlite",
+            Self::Mongodb => "mongodb",
+        }
+    }
+}
+
+/// Version of the stored data layout. Bump it together with a new migration
+/// in each backend whenever stored documents change shape.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Which stored commits to read: always scoped to one repository.
+#[derive(Debug, Clone, Default)]
+pub struct CommitQuery {
+    pub repo_id: String,
+    pub filter: CommitFilter,
+    /// Keep only the newest `limit` matches.
+    pub limit: Option<usize>,
+}
+
+/// One generated narrative, kept so runs can be compared later.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Artifact {
+    pub id: String,
+    pub repo_id: String,
+    pub command: String,
+    /// Flags that shaped the output, e.g. `audience = exec`.
+    pub parameters: BTreeMap<String, String>,
+    /// Model that wrote the text, or `offline`.
+    pub model: String,
+    /// Oldest and newest commit of the input.
+    pub first_commit: Option<String>,
+    pub last_commit: Option<String>,
+    pub commit_count: i64,
+    pub output: String,
+    pub timestamp: i64,
+}
+
+/// Which artifacts to read, newest first.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactQuery {
+    pub repo_id: Option<String>,
+    pub command: Option<String>,
+    /// Matches ids starting with this text.
+    pub id_prefix: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Aggregate numbers over a repository's stored commits.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepoStats {
+    pub commits: i64,
+    /// Distinct calendar days (UTC) with at least one commit.
+    pub active_days: i64,
+    pub first_commit_at: Option<i64>,
+    pub last_commit_at: Option<i64>,
+    /// Commits per author per ISO week, oldest week first.
+    pub authors_by_week: Vec<AuthorWeek>,
+    /// Files changed most often, most changed first.
+    pub top_paths: Vec<PathChanges>,
+    /// Commit-type mix per month, oldest month first.
+    pub kinds_by_month: Vec<KindMonth>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorWeek {
+    /// ISO week, e.g. `2024-W05`.
+    pub week: String,
+    pub author: String,
+    pub commits: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathChanges {
+    pub path: String,
+    pub changes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KindMonth {
+    /// e.g. `2024-01`.
+    pub month: String,
+    pub kind: String,
+    pub commits: i64,
+}
+
+/// Commits written per batch, between progress updates.
+const WRITE_BATCH_SIZE: usize = 500;
+
+/// Long-term project memory: repositories and their commits.
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// Upserts the repository's entry, keyed by its id.
+    async fn save_repo(&self, repo: &RepoEntry) -> Result<()>;
+    
+    async fn list_repos(&self) -> Result<Vec<RepoEntry>>;
+    
+    /// Upserts `commits` in batches, calling `on_progress(stored, total)` after each batch.
+    async fn save_commits(
+        &self,
+        repo_id: &str,
+        commits: &[Commit],
+        on_progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Result<()>;
+    
+    /// Ids of every repository with stored commits, including ones without a
+    /// repository entry, like MongoDB's `legacy` commits from before repo scoping.
+    async fn commit_repo_ids(&self) -> Result<Vec<String>>;
+    
+    /// Removes every stored commit of the repository, before its rewritten
+    /// history is stored again. Returns how many were removed.
+    async fn delete_commits(&self, repo_id: &str) -> Result<u64>;
+    
+    /// Commits matching `query`, newest first.
+    async fn find_commits(&self, query: &CommitQuery) -> Result<Vec<Commit>>;
+    
+    /// Aggregates over the commits matching `query`; `top_paths` keeps `top` entries.
+    async fn stats(&self, query: &CommitQuery, top: usize) -> Result<RepoStats>;
+    
+    /// Upserts a generated output, keyed by its id.
+    async fn save_artifact(&self, artifact: &Artifact) -> Result<()>;
+    
+    /// Artifacts matching `query`, newest first.
+    async fn find_artifacts(&self, query: &ArtifactQuery) -> Result<Vec<Artifact>>;
+}
+
+/// Opens the store selected by `STORE_BACKEND`.
+pub async fn open(config: &Config) -> Result<Box<dyn Store>> {
+    open_backend(Backend::parse(&config.store_backend)?, config).await
+}
+
+pub async fn open_backend(backend: Backend, config: &Config) -> Result<Box<dyn Store>> {
+    Ok(match backend {
+        Backend::Sqlite => Box::new(SqliteStore::open(&config.store_path)?),
+        Backend::Mongodb => Box::new(MongoStore::new(config).await?),
+    })
+}
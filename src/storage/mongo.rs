@@ -0,0 +1,564 @@
+use anyhow::{Result, Context};
+use async_trait::async_trait;
+use colored::*;
+use mongodb::{Client, Collection, Database, IndexModel};
+use mongodb::bson::{self, doc, Bson, Document};
+use mongodb::options::{ClientOptions, IndexOptions, Tls, TlsOptions};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::config::Config;
+use crate::repository::{Commit, CommitKind};
+use crate::workspace::RepoEntry;
+use super::{
+    Artifact, ArtifactQuery, AuthorWeek, CommitQuery, KindMonth, PathChanges, RepoStats, Store,
+    SCHEMA_VERSION, WRITE_BATCH_SIZE,
+};
+
+const REPOS_COLLECTION: &str = "repos";
+const ARTIFACTS_COLLECTION: &str = "artifacts";
+const META_COLLECTION: &str = "meta";
+
+/// Owner of commits stored before documents were scoped by repository.
+const LEGACY_REPO_ID: &str = "legacy";
+
+/// A commit as stored: the same hash can exist in several repositories
+/// (forks, mirrors), so documents are keyed by `(repo_id, hash)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitDocument {
+    pub repo_id: String,
+    /// Stored so queries can filter by type without re-parsing messages.
+    #[serde(default)]
+    pub kind: CommitKind,
+    #[serde(flatten)]
+    pub commit: Commit,
+}
+
+/// Any stored document, tagged with the schema version it was written under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Versioned<T> {
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(flatten)]
+    document: T,
+}
+
+impl<T> Versioned<T> {
+    fn current(document: T) -> Self {
+        Self { schema_version: SCHEMA_VERSION, document }
+    }
+}
+
+pub struct MongoStore {
+    db: Database,
+    commits: Collection<Versioned<CommitDocument>>,
+    repos: Collection<Versioned<RepoEntry>>,
+    artifacts: Collection<Versioned<Artifact>>,
+}
+
+impl MongoStore {
+    pub async fn new(config: &Config) -> Result<Self> {
+        let client = Client::with_options(client_options(config).await?)
+            .context("Failed to connect to MongoDB")?;
+        
+        // Ping the database we'll use: locked-down users often can't run commands on `admin`
+        let db = client.database(&config.mongodb_db);
+        db.run_command(doc! { "ping": 1 })
+            .await
+            .with_context(|| format!("Failed to ping MongoDB database '{}'", config.mongodb_db))?;
+        
+        let commits = db.collection(&config.mongodb_collection);
+        let repos = db.collection(REPOS_COLLECTION);
+        let artifacts = db.collection(ARTIFACTS_COLLECTION);
+        
+        let store = Self { db, commits, repos, artifacts };
+        store.migrate().await?;
+        store.ensure_indexes().await?;
+        
+        Ok(store)
+    }
+    
+    /// Brings documents written by older versions up to `SCHEMA_VERSION`.
+    /// The applied version is kept in the `meta` collection.
+    async fn migrate(&self) -> Result<()> {
+        let meta = self.db.collection::<Document>(META_COLLECTION);
+        let current = meta
+            .find_one(doc! { "_id": "schema" })
+            .await
+            .context("Failed to read store schema version")?
+            .and_then(|doc| doc.get_i64("version").ok())
+            .unwrap_or(0) as u32;
+        
+        if current > SCHEMA_VERSION {
+            anyhow::bail!(
+                "The MongoDB store uses schema v{}, but this whisper only knows v{}. Please upgrade whisper.",
+                current,
+                SCHEMA_VERSION
+            );
+        }
+        
+        for version in (current + 1)..=SCHEMA_VERSION {
+            self.apply_migration(version)
+                .await
+                .with_context(|| format!("Failed to migrate the MongoDB store to schema v{}", version))?;
+            
+            meta.replace_one(doc! { "_id": "schema" }, doc! { "_id": "schema", "version": version as i64 })
+                .upsert(true)
+                .await?;
+            
+            println!("{}", format!("🔧 Upgraded MongoDB store to schema v{}", version).dimmed());
+        }
+        
+        Ok(())
+    }
+    
+    async fn apply_migration(&self, version: u32) -> Result<()> {
+        match version {
+            // Commits from before repository scoping lack `repo_id` and `kind`;
+            // nothing carried a schema version yet.
+            1 => {
+                let commits = self.db.collection::<Document>(self.commits.name());
+                
+                commits
+                    .update_many(doc! { "repo_id": { "$exists": false } }, doc! { "$set": { "repo_id": LEGACY_REPO_ID } })
+                    .await?;
+                
+                let mut cursor = commits
+                    .find(doc! { "kind": { "$exists": false } })
+                    .projection(doc! { "message": 1 })
+                    .await?;
+                
+                let mut updates = Vec::new();
+                use futures::stream::StreamExt;
+                while let Some(document) = cursor.next().await {
+                    let document = document?;
+                    let commit = Commit {
+                        message: document.get_str("message").unwrap_or_default().to_string(),
+                        ..Default::default()
+                    };
+                    updates.push(doc! {
+                        "q": { "_id": document.get("_id").cloned().unwrap_or_default() },
+                        "u": { "$set": { "kind": bson::to_bson(&commit.kind())? } },
+                    });
+                }
+                
+                for batch in updates.chunks(WRITE_BATCH_SIZE) {
+                    self.run_updates(self.commits.name(), batch.to_vec()).await?;
+                }
+                
+                for name in [self.commits.name(), self.repos.name(), self.artifacts.name()] {
+                    self.db
+                        .collection::<Document>(name)
+                        .update_many(
+                            doc! { "schema_version": { "$exists": false } },
+                            doc! { "$set": { "schema_version": 1 } },
+                        )
+                        .await?;
+                }
+            }
+            _ => unreachable!("no migration defined for schema v{}", version),
+        }
+        
+        Ok(())
+    }
+    
+    /// Sends `updates` as one unordered `update` command.
+    async fn run_updates(&self, collection: &str, updates: Vec<Document>) -> Result<()> {
+        let reply = self.db
+            .run_command(doc! {
+                "update": collection,
+                "updates": updates,
+                "ordered": false,
+            })
+            .await?;
+        
+        check_write_errors(&reply)
+    }
+    
+    /// Creates the indexes queries rely on. Idempotent - existing indexes are left alone.
+    async fn ensure_indexes(&self) -> Result<()> {
+        let commit_indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "repo_id": 1, "hash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder().keys(doc! { "repo_id": 1, "timestamp": -1 }).build(),
+            IndexModel::builder().keys(doc! { "repo_id": 1, "author": 1 }).build(),
+        ];
+        
+        self.commits
+            .create_indexes(commit_indexes)
+            .await
+            .context("Failed to create commit indexes")?;
+        
+        self.repos
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "id": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .context("Failed to create repository index")?;
+        
+        self.artifacts
+            .create_indexes(vec![
+                IndexModel::builder()
+                    .keys(doc! { "id": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+                IndexModel::builder().keys(doc! { "repo_id": 1, "timestamp": -1 }).build(),
+            ])
+            .await
+            .context("Failed to create artifact indexes")?;
+        
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Store for MongoStore {
+    async fn save_repo(&self, repo: &RepoEntry) -> Result<()> {
+        self.repos
+            .replace_one(doc! { "id": &repo.id }, Versioned::current(repo.clone()))
+            .upsert(true)
+            .await
+            .with_context(|| format!("Failed to store repository '{}'", repo.name))?;
+        
+        Ok(())
+    }
+    
+    async fn list_repos(&self) -> Result<Vec<RepoEntry>> {
+        let mut cursor = self.repos.find(doc! {}).sort(doc! { "added_at": 1 }).await?;
+        let mut repos = Vec::new();
+        
+        use futures::stream::StreamExt;
+        while let Some(result) = cursor.next().await {
+            repos.push(result?.document);
+        }
+        
+        Ok(repos)
+    }
+    
+    async fn save_commits(
+        &self,
+        repo_id: &str,
+        commits: &[Commit],
+        on_progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Result<()> {
+        if commits.is_empty() {
+            return Ok(());
+        }
+        
+        let mut stored = 0;
+        
+        for batch in commits.chunks(WRITE_BATCH_SIZE) {
+            let mut updates = Vec::with_capacity(batch.len());
+            
+            for commit in batch {
+                let document = Versioned::current(CommitDocument {
+                    repo_id: repo_id.to_string(),
+                    kind: commit.kind(),
+                    commit: commit.clone(),
+                });
+                
+                updates.push(doc! {
+                    "q": { "repo_id": repo_id, "hash": &commit.hash },
+                    "u": bson::to_document(&document)?,
+                    "upsert": true,
+                });
+            }
+            
+            self.run_updates(self.commits.name(), updates)
+                .await
+                .context("Failed to store commits")?;
+            
+            stored += batch.len();
+            on_progress(stored, commits.len());
+        }
+        
+        Ok(())
+    }
+    
+    async fn commit_repo_ids(&self) -> Result<Vec<String>> {
+        let ids = self.commits
+            .distinct("repo_id", doc! {})
+            .await
+            .context("Failed to list repositories with commits")?;
+        
+        Ok(ids.iter().filter_map(|id| id.as_str().map(str::to_string)).collect())
+    }
+    
+    async fn delete_commits(&self, repo_id: &str) -> Result<u64> {
+        let result = self.commits
+            .delete_many(doc! { "repo_id": repo_id })
+            .await
+            .context("Failed to delete commits")?;
+        
+        Ok(result.deleted_count)
+    }
+    
+    async fn find_commits(&self, query: &CommitQuery) -> Result<Vec<Commit>> {
+        let mut find = self.commits
+            .find(query_document(query))
+            .sort(doc! { "timestamp": -1, "hash": 1 });
+        
+        if let Some(limit) = query.limit {
+            find = find.limit(limit as i64);
+        }
+        
+        let mut cursor = find.await.context("Failed to query commits")?;
+        let mut commits = Vec::new();
+        
+        use futures::stream::StreamExt;
+        while let Some(result) = cursor.next().await {
+            commits.push(result?.document.commit);
+        }
+        
+        Ok(commits)
+    }
+    
+    async fn stats(&self, query: &CommitQuery, top: usize) -> Result<RepoStats> {
+        let day = |format: &str| doc! { "$dateToString": { "format": format, "date": "$date" } };
+        
+        let pipeline = vec![
+            doc! { "$match": query_document(query) },
+            doc! { "$addFields": { "date": { "$toDate": { "$multiply": ["$timestamp", 1000_i64] } } } },
+            doc! { "$facet": {
+                "totals": [
+                    { "$group": {
+                        "_id": Bson::Null,
+                        "commits": { "$sum": 1 },
+                        "first": { "$min": "$timestamp" },
+                        "last": { "$max": "$timestamp" },
+                        "days": { "$addToSet": day("%Y-%m-%d") },
+                    } },
+                    { "$project": { "commits": 1, "first": 1, "last": 1, "active_days": { "$size": "$days" } } },
+                ],
+                "authors_by_week": [
+                    { "$group": { "_id": { "week": day("%G-W%V"), "author": "$author" }, "commits": { "$sum": 1 } } },
+                    { "$sort": { "_id.week": 1, "commits": -1, "_id.author": 1 } },
+                ],
+                "top_paths": [
+                    { "$unwind": "$files_changed" },
+                    { "$group": { "_id": "$files_changed.file_path", "changes": { "$sum": 1 } } },
+                    { "$sort": { "changes": -1, "_id": 1 } },
+                    // The server rejects a zero limit; `top` is applied again below
+                    { "$limit": top.max(1) as i64 },
+                ],
+                "kinds_by_month": [
+                    { "$group": { "_id": { "month": day("%Y-%m"), "kind": "$kind" }, "commits": { "$sum": 1 } } },
+                    { "$sort": { "_id.month": 1, "commits": -1, "_id.kind": 1 } },
+                ],
+            } },
+        ];
+        
+        let mut cursor = self.commits
+            .aggregate(pipeline)
+            .await
+            .context("Failed to aggregate commit statistics")?;
+        
+        use futures::stream::StreamExt;
+        let facets = cursor.next().await.transpose()?.unwrap_or_default();
+        let facet = |name: &str| -> Vec<Document> {
+            facets
+                .get_array(name)
+                .map(|items| items.iter().filter_map(|item| item.as_document().cloned()).collect())
+                .unwrap_or_default()
+        };
+        let key = |doc: &Document, field: &str| {
+            doc.get_document("_id")
+                .ok()
+                .and_then(|id| id.get_str(field).ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        
+        let mut stats = RepoStats::default();
+        
+        if let Some(totals) = facet("totals").first() {
+            stats.commits = int(totals, "commits");
+            stats.active_days = int(totals, "active_days");
+            stats.first_commit_at = Some(int(totals, "first"));
+            stats.last_commit_at = Some(int(totals, "last"));
+        }
+        
+        stats.authors_by_week = facet("authors_by_week")
+            .iter()
+            .map(|doc| AuthorWeek {
+                week: key(doc, "week"),
+                author: key(doc, "author"),
+                commits: int(doc, "commits"),
+            })
+            .collect();
+        
+        stats.top_paths = facet("top_paths")
+            .iter()
+            .map(|doc| PathChanges {
+                path: doc.get_str("_id").unwrap_or_default().to_string(),
+                changes: int(doc, "changes"),
+            })
+            .take(top)
+            .collect();
+        
+        stats.kinds_by_month = facet("kinds_by_month")
+            .iter()
+            .map(|doc| KindMonth {
+                month: key(doc, "month"),
+                kind: key(doc, "kind"),
+                commits: int(doc, "commits"),
+            })
+            .collect();
+        
+        Ok(stats)
+    }
+    
+    async fn save_artifact(&self, artifact: &Artifact) -> Result<()> {
+        self.artifacts
+            .replace_one(doc! { "id": &artifact.id }, Versioned::current(artifact.clone()))
+            .upsert(true)
+            .await
+            .context("Failed to store generated output")?;
+        
+        Ok(())
+    }
+    
+    async fn find_artifacts(&self, query: &ArtifactQuery) -> Result<Vec<Artifact>> {
+        let mut filter = Document::new();
+        if let Some(repo_id) = &query.repo_id {
+            filter.insert("repo_id", repo_id);
+        }
+        if let Some(command) = &query.command {
+            filter.insert("command", command);
+        }
+        if let Some(prefix) = &query.id_prefix {
+            filter.insert("id", doc! { "$regex": format!("^{}", regex::escape(prefix)) });
+        }
+        
+        let mut find = self.artifacts.find(filter).sort(doc! { "timestamp": -1, "id": -1 });
+        if let Some(limit) = query.limit {
+            find = find.limit(limit as i64);
+        }
+        
+        let mut cursor = find.await.context("Failed to query generated outputs")?;
+        let mut artifacts = Vec::new();
+        
+        use futures::stream::StreamExt;
+        while let Some(result) = cursor.next().await {
+            artifacts.push(result?.document);
+        }
+        
+        Ok(artifacts)
+    }
+}
+
+fn query_document(query: &CommitQuery) -> Document {
+    let filter = &query.filter;
+    let mut document = doc! { "repo_id": &query.repo_id };
+    
+    let mut timestamp = Document::new();
+    if let Some(after) = filter.after {
+        timestamp.insert("$gte", after);
+    }
+    if let Some(before) = filter.before {
+        timestamp.insert("$lt", before);
+    }
+    if !timestamp.is_empty() {
+        document.insert("timestamp", timestamp);
+    }
+    
+    if let Some(author) = &filter.author {
+        document.insert("author", doc! { "$regex": regex::escape(author), "$options": "i" });
+    }
+    if let Some(path) = &filter.path {
+        document.insert("files_changed.file_path", doc! { "$regex": format!("^{}", regex::escape(path)) });
+    }
+    if let Some(kind) = filter.kind {
+        document.insert("kind", bson::to_bson(&kind).unwrap_or_default());
+    }
+    
+    document
+}
+
+/// Driver options from `MONGODB_URL`, with the `MONGODB_*` settings filling in
+/// whatever the URL leaves unset.
+async fn client_options(config: &Config) -> Result<ClientOptions> {
+    let mut options = ClientOptions::parse(&config.mongodb_url)
+        .await
+        .context("Invalid MONGODB_URL")?;
+    
+    options.server_selection_timeout.get_or_insert(Duration::from_secs(config.mongodb_server_selection_timeout_secs));
+    options.connect_timeout.get_or_insert(Duration::from_secs(config.mongodb_connect_timeout_secs));
+    
+    if options.app_name.is_none() && !config.mongodb_app_name.is_empty() {
+        options.app_name = Some(config.mongodb_app_name.clone());
+    }
+    
+    if !config.mongodb_auth_source.is_empty() {
+        match options.credential.as_mut() {
+            Some(credential) => {
+                credential.source.get_or_insert_with(|| config.mongodb_auth_source.clone());
+            }
+            None => anyhow::bail!("MONGODB_AUTH_SOURCE is set but MONGODB_URL has no username"),
+        }
+    }
+    
+    let file = |path: &str, name: &str| -> Result<Option<std::path::PathBuf>> {
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let path = std::path::PathBuf::from(path);
+        if !path.is_file() {
+            anyhow::bail!("{} points to {}, which is not a file", name, path.display());
+        }
+        Ok(Some(path))
+    };
+    let ca_file = file(&config.mongodb_tls_ca_file, "MONGODB_TLS_CA_FILE")?;
+    let cert_key_file = file(&config.mongodb_tls_cert_key_file, "MONGODB_TLS_CERT_KEY_FILE")?;
+    
+    match options.tls.as_mut() {
+        Some(Tls::Enabled(tls)) => {
+            tls.ca_file_path = tls.ca_file_path.take().or(ca_file);
+            tls.cert_key_file_path = tls.cert_key_file_path.take().or(cert_key_file);
+        }
+        Some(Tls::Disabled) => {
+            if ca_file.is_some() || cert_key_file.is_some() {
+                anyhow::bail!("MONGODB_URL disables TLS, but TLS certificate files are configured");
+            }
+        }
+        None => {
+            if config.mongodb_tls || ca_file.is_some() || cert_key_file.is_some() {
+                let mut tls = TlsOptions::default();
+                tls.ca_file_path = ca_file;
+                tls.cert_key_file_path = cert_key_file;
+                options.tls = Some(Tls::Enabled(tls));
+            }
+        }
+    }
+    
+    Ok(options)
+}
+
+/// A numeric field, whichever integer width the server chose for it.
+fn int(doc: &Document, field: &str) -> i64 {
+    match doc.get(field) {
+        Some(Bson::Int32(n)) => *n as i64,
+        Some(Bson::Int64(n)) => *n,
+        Some(Bson::Double(n)) => *n as i64,
+        _ => 0,
+    }
+}
+
+/// Raw write commands report per-document failures in the reply rather than as an error.
+fn check_write_errors(reply: &Document) -> Result<()> {
+    let Ok(errors) = reply.get_array("writeErrors") else {
+        return Ok(());
+    };
+    
+    let first = errors
+        .first()
+        .and_then(|e| e.as_document())
+        .and_then(|e| e.get_str("errmsg").ok())
+        .unwrap_or("unknown error");
+    
+    anyhow::bail!("{} document(s) failed to write: {}", errors.len(), first)
+}
@@ -0,0 +1,569 @@
+use anyhow::{Result, Context};
+use async_trait::async_trait;
+use colored::*;
+use rusqlite::{params, params_from_iter, Connection};
+use rusqlite::types::Value;
+use std::path::Path;
+use std::sync::Mutex;
+use crate::repository::{Commit, FileChange};
+use crate::workspace::RepoEntry;
+use super::{
+    Artifact, ArtifactQuery, AuthorWeek, CommitQuery, KindMonth, PathChanges, RepoStats, Store,
+    SCHEMA_VERSION, WRITE_BATCH_SIZE,
+};
+
+/// Schema changes, applied in order; entry `n` brings the database to version `n + 1`.
+/// The applied version is kept in SQLite's `user_version`.
+const MIGRATIONS: [&str; SCHEMA_VERSION as usize] = [SCHEMA_V1];
+
+const SCHEMA_V1: &str = "
+    CREATE TABLE IF NOT EXISTS repos (
+        id       TEXT PRIMARY KEY,
+        name     TEXT NOT NULL,
+        path     TEXT NOT NULL,
+        url      TEXT,
+        added_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS commits (
+        repo_id   TEXT NOT NULL,
+        hash      TEXT NOT NULL,
+        kind      TEXT NOT NULL,
+        message   TEXT NOT NULL,
+        author    TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        PRIMARY KEY (repo_id, hash)
+    );
+
+    CREATE INDEX IF NOT EXISTS commits_by_time ON commits (repo_id, timestamp DESC);
+    CREATE INDEX IF NOT EXISTS commits_by_author ON commits (repo_id, author);
+
+    CREATE TABLE IF NOT EXISTS commit_files (
+        repo_id   TEXT NOT NULL,
+        hash      TEXT NOT NULL,
+        position  INTEGER NOT NULL,
+        file_path TEXT NOT NULL,
+        summary   TEXT NOT NULL,
+        PRIMARY KEY (repo_id, hash, position)
+    );
+
+    CREATE INDEX IF NOT EXISTS commit_files_by_path ON commit_files (repo_id, file_path);
+
+    CREATE TABLE IF NOT EXISTS artifacts (
+        id           TEXT PRIMARY KEY,
+        repo_id      TEXT NOT NULL,
+        command      TEXT NOT NULL,
+        parameters   TEXT NOT NULL,
+        model        TEXT NOT NULL,
+        first_commit TEXT,
+        last_commit  TEXT,
+        commit_count INTEGER NOT NULL,
+        output       TEXT NOT NULL,
+        timestamp    INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS artifacts_by_time ON artifacts (repo_id, timestamp DESC);
+";
+
+/// Project memory in a single local database file - the default, no server needed.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut conn = Connection::open(path)
+            .with_context(|| format!("Failed to open local store {}", path))?;
+
+        migrate(&mut conn)?;
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn save_repo(&self, repo: &RepoEntry) -> Result<()> {
+        self.conn()
+            .execute(
+                "INSERT INTO repos (id, name, path, url, added_at) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (id) DO UPDATE SET
+                     name = excluded.name, path = excluded.path,
+                     url = excluded.url, added_at = excluded.added_at",
+                params![repo.id, repo.name, repo.path, repo.url, repo.added_at],
+            )
+            .with_context(|| format!("Failed to store repository '{}'", repo.name))?;
+
+        Ok(())
+    }
+
+    async fn list_repos(&self) -> Result<Vec<RepoEntry>> {
+        let conn = self.conn();
+        let mut statement = conn.prepare("SELECT id, name, path, url, added_at FROM repos ORDER BY added_at")?;
+
+        let repos = statement
+            .query_map([], |row| {
+                Ok(RepoEntry {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    path: row.get(2)?,
+                    url: row.get(3)?,
+                    added_at: row.get(4)?,
+                    // Clone ownership and sync state are facts about this machine, not kept in the store
+                    ..Default::default()
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(repos)
+    }
+
+    async fn save_commits(
+        &self,
+        repo_id: &str,
+        commits: &[Commit],
+        on_progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Result<()> {
+        let mut conn = self.conn();
+        let mut stored = 0;
+
+        for batch in commits.chunks(WRITE_BATCH_SIZE) {
+            let tx = conn.transaction()?;
+
+            for commit in batch {
+                tx.execute(
+                    "INSERT OR REPLACE INTO commits (repo_id, hash, kind, message, author, timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![repo_id, commit.hash, commit.kind().name(), commit.message, commit.author, commit.timestamp],
+                )?;
+
+                tx.execute(
+                    "DELETE FROM commit_files WHERE repo_id = ?1 AND hash = ?2",
+                    params![repo_id, commit.hash],
+                )?;
+
+                for (position, file) in commit.files_changed.iter().enumerate() {
+                    tx.execute(
+                        "INSERT INTO commit_files (repo_id, hash, position, file_path, summary)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![repo_id, commit.hash, position as i64, file.file_path, file.summary],
+                    )?;
+                }
+            }
+
+            tx.commit().context("Failed to store commits")?;
+
+            stored += batch.len();
+            on_progress(stored, commits.len());
+        }
+
+        Ok(())
+    }
+
+    async fn commit_repo_ids(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut statement = conn.prepare("SELECT DISTINCT repo_id FROM commits ORDER BY repo_id")?;
+        let ids = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        Ok(ids)
+    }
+
+    async fn delete_commits(&self, repo_id: &str) -> Result<u64> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM commit_files WHERE repo_id = ?1", params![repo_id])?;
+        let deleted = tx.execute("DELETE FROM commits WHERE repo_id = ?1", params![repo_id])?;
+        tx.commit().context("Failed to delete commits")?;
+
+        Ok(deleted as u64)
+    }
+
+    async fn find_commits(&self, query: &CommitQuery) -> Result<Vec<Commit>> {
+        let (condition, values) = commit_condition(query);
+        let mut sql = format!(
+            "SELECT c.hash, c.message, c.author, c.timestamp FROM commits c WHERE {} ORDER BY c.timestamp DESC, c.hash",
+            condition
+        );
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let conn = self.conn();
+        let mut statement = conn.prepare(&sql)?;
+        let mut commits = statement
+            .query_map(params_from_iter(values), |row| {
+                Ok(Commit {
+                    hash: row.get(0)?,
+                    message: row.get(1)?,
+                    author: row.get(2)?,
+                    timestamp: row.get(3)?,
+                    files_changed: Vec::new(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query commits")?;
+
+        let mut files = conn.prepare(
+            "SELECT file_path, summary FROM commit_files WHERE repo_id = ?1 AND hash = ?2 ORDER BY position",
+        )?;
+
+        for commit in &mut commits {
+            commit.files_changed = files
+                .query_map(params![query.repo_id, commit.hash], |row| {
+                    Ok(FileChange {
+                        file_path: row.get(0)?,
+                        summary: row.get(1)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+
+        Ok(commits)
+    }
+
+    async fn stats(&self, query: &CommitQuery, top: usize) -> Result<RepoStats> {
+        let (condition, values) = commit_condition(query);
+        let conn = self.conn();
+
+        let mut stats = conn.query_row(
+            &format!(
+                "SELECT COUNT(*), COUNT(DISTINCT date(c.timestamp, 'unixepoch')), MIN(c.timestamp), MAX(c.timestamp)
+                 FROM commits c WHERE {}",
+                condition
+            ),
+            params_from_iter(values.iter()),
+            |row| {
+                Ok(RepoStats {
+                    commits: row.get(0)?,
+                    active_days: row.get(1)?,
+                    first_commit_at: row.get(2)?,
+                    last_commit_at: row.get(3)?,
+                    ..Default::default()
+                })
+            },
+        )?;
+
+        stats.authors_by_week = conn
+            .prepare(&format!(
+                "SELECT strftime('%G-W%V', c.timestamp, 'unixepoch') AS week, c.author, COUNT(*) AS n
+                 FROM commits c WHERE {}
+                 GROUP BY week, c.author ORDER BY week, n DESC, c.author",
+                condition
+            ))?
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok(AuthorWeek {
+                    week: row.get(0)?,
+                    author: row.get(1)?,
+                    commits: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        stats.top_paths = conn
+            .prepare(&format!(
+                "SELECT f.file_path, COUNT(*) AS n
+                 FROM commit_files f JOIN commits c ON c.repo_id = f.repo_id AND c.hash = f.hash
+                 WHERE {}
+                 GROUP BY f.file_path ORDER BY n DESC, f.file_path LIMIT {}",
+                condition, top
+            ))?
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok(PathChanges {
+                    path: row.get(0)?,
+                    changes: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        stats.kinds_by_month = conn
+            .prepare(&format!(
+                "SELECT strftime('%Y-%m', c.timestamp, 'unixepoch') AS month, c.kind, COUNT(*) AS n
+                 FROM commits c WHERE {}
+                 GROUP BY month, c.kind ORDER BY month, n DESC, c.kind",
+                condition
+            ))?
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok(KindMonth {
+                    month: row.get(0)?,
+                    kind: row.get(1)?,
+                    commits: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(stats)
+    }
+
+    async fn save_artifact(&self, artifact: &Artifact) -> Result<()> {
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO artifacts (id, repo_id, command, parameters, model, first_commit, last_commit, commit_count, output, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    artifact.id,
+                    artifact.repo_id,
+                    artifact.command,
+                    serde_json::to_string(&artifact.parameters)?,
+                    artifact.model,
+                    artifact.first_commit,
+                    artifact.last_commit,
+                    artifact.commit_count,
+                    artifact.output,
+                    artifact.timestamp,
+                ],
+            )
+            .context("Failed to store generated output")?;
+
+        Ok(())
+    }
+
+    async fn find_artifacts(&self, query: &ArtifactQuery) -> Result<Vec<Artifact>> {
+        let mut sql = String::from(
+            "SELECT id, repo_id, command, parameters, model, first_commit, last_commit, commit_count, output, timestamp
+             FROM artifacts WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+
+        if let Some(repo_id) = &query.repo_id {
+            sql.push_str(" AND repo_id = ?");
+            values.push(repo_id.clone().into());
+        }
+        if let Some(command) = &query.command {
+            sql.push_str(" AND command = ?");
+            values.push(command.clone().into());
+        }
+        if let Some(prefix) = &query.id_prefix {
+            sql.push_str(" AND substr(id, 1, length(?)) = ?");
+            values.push(prefix.clone().into());
+            values.push(prefix.clone().into());
+        }
+
+        sql.push_str(" ORDER BY timestamp DESC, id DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let conn = self.conn();
+        let mut statement = conn.prepare(&sql)?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| {
+                Ok((
+                    Artifact {
+                        id: row.get(0)?,
+                        repo_id: row.get(1)?,
+                        command: row.get(2)?,
+                        parameters: Default::default(),
+                        model: row.get(4)?,
+                        first_commit: row.get(5)?,
+                        last_commit: row.get(6)?,
+                        commit_count: row.get(7)?,
+                        output: row.get(8)?,
+                        timestamp: row.get(9)?,
+                    },
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query generated outputs")?;
+
+        rows.into_iter()
+            .map(|(mut artifact, parameters)| {
+                artifact.parameters = serde_json::from_str(&parameters)?;
+                Ok(artifact)
+            })
+            .collect()
+    }
+}
+
+/// SQL condition over `commits c` selecting what `query` asks for, and its parameters.
+fn commit_condition(query: &CommitQuery) -> (String, Vec<Value>) {
+    let filter = &query.filter;
+    let mut sql = String::from("c.repo_id = ?");
+    let mut values: Vec<Value> = vec![query.repo_id.clone().into()];
+
+    if let Some(after) = filter.after {
+        sql.push_str(" AND c.timestamp >= ?");
+        values.push(after.into());
+    }
+    if let Some(before) = filter.before {
+        sql.push_str(" AND c.timestamp < ?");
+        values.push(before.into());
+    }
+    if let Some(author) = &filter.author {
+        sql.push_str(" AND instr(lower(c.author), lower(?)) > 0");
+        values.push(author.clone().into());
+    }
+    if let Some(path) = &filter.path {
+        sql.push_str(
+            " AND EXISTS (SELECT 1 FROM commit_files cf
+               WHERE cf.repo_id = c.repo_id AND cf.hash = c.hash
+               AND substr(cf.file_path, 1, length(?)) = ?)",
+        );
+        values.push(path.clone().into());
+        values.push(path.clone().into());
+    }
+    if let Some(kind) = filter.kind {
+        sql.push_str(" AND c.kind = ?");
+        values.push(kind.name().into());
+    }
+
+    (sql, values)
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    if current > SCHEMA_VERSION {
+        anyhow::bail!(
+            "The local store uses schema v{}, but this whisper only knows v{}. Please upgrade whisper.",
+            current,
+            SCHEMA_VERSION
+        );
+    }
+
+    for version in (current + 1)..=SCHEMA_VERSION {
+        let tx = conn.transaction()?;
+        tx.execute_batch(MIGRATIONS[version as usize - 1])
+            .and_then(|_| tx.pragma_update(None, "user_version", version))
+            .with_context(|| format!("Failed to migrate the local store to schema v{}", version))?;
+        tx.commit()?;
+
+        // A fresh database is created at the latest version; only report real upgrades
+        if current > 0 {
+            println!("{}", format!("🔧 Upgraded local store to schema v{}", version).dimmed());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{CommitFilter, CommitKind};
+
+    const JAN_1: i64 = 1704067200; // 2024-01-01, a Monday
+    const DAY: i64 = 86400;
+
+    fn commit(hash: &str, message: &str, author: &str, timestamp: i64, files: &[&str]) -> Commit {
+        Commit {
+            hash: hash.to_string(),
+            message: message.to_string(),
+            author: author.to_string(),
+            timestamp,
+            files_changed: files
+                .iter()
+                .map(|path| FileChange { file_path: path.to_string(), summary: format!("edit {}", path) })
+                .collect(),
+        }
+    }
+
+    async fn store() -> SqliteStore {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let commits = [
+            commit("a1", "feat: login form", "Ada Lovelace", JAN_1, &["src/auth/login.rs", "README.md"]),
+            commit("b2", "fix: crash on empty password", "Grace Hopper", JAN_1 + DAY, &["src/auth/login.rs"]),
+            commit("c3", "docs: explain setup", "Ada Lovelace", JAN_1 + 35 * DAY, &["docs/setup.md"]),
+        ];
+        store.save_commits("repo", &commits, &|_, _| {}).await.unwrap();
+        store.save_commits("other", &[commit("d4", "feat: elsewhere", "Ada Lovelace", JAN_1, &["src/auth/x.rs"])], &|_, _| {}).await.unwrap();
+        store
+    }
+
+    async fn hashes(store: &SqliteStore, filter: CommitFilter) -> Vec<String> {
+        let query = CommitQuery { repo_id: "repo".to_string(), filter, limit: None };
+        store.find_commits(&query).await.unwrap().into_iter().map(|c| c.hash).collect()
+    }
+
+    #[test]
+    fn migrations_bring_a_new_database_to_the_latest_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("whisper.db");
+        let path = path.to_str().unwrap();
+
+        SqliteStore::open(path).unwrap();
+        let conn = Connection::open(path).unwrap();
+        let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        // Reopening an up-to-date store is a no-op, a newer one is refused
+        SqliteStore::open(path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        assert!(SqliteStore::open(path).is_err());
+    }
+
+    #[tokio::test]
+    async fn commits_round_trip_newest_first_with_their_files() {
+        let store = store().await;
+        let commits = store
+            .find_commits(&CommitQuery { repo_id: "repo".to_string(), filter: CommitFilter::default(), limit: Some(2) })
+            .await
+            .unwrap();
+
+        assert_eq!(commits.iter().map(|c| c.hash.as_str()).collect::<Vec<_>>(), ["c3", "b2"]);
+        assert_eq!(commits[1].files_changed.len(), 1);
+        assert_eq!(commits[1].files_changed[0].summary, "edit src/auth/login.rs");
+    }
+
+    #[tokio::test]
+    async fn filters_narrow_the_commits() {
+        let store = store().await;
+
+        let by_author = CommitFilter { author: Some("ada".to_string()), ..Default::default() };
+        assert_eq!(hashes(&store, by_author).await, ["c3", "a1"]);
+
+        let by_path = CommitFilter { path: Some("src/auth".to_string()), ..Default::default() };
+        assert_eq!(hashes(&store, by_path).await, ["b2", "a1"]);
+
+        let by_kind = CommitFilter { kind: Some(CommitKind::Fix), ..Default::default() };
+        assert_eq!(hashes(&store, by_kind).await, ["b2"]);
+
+        let by_dates = CommitFilter { after: Some(JAN_1 + DAY), before: Some(JAN_1 + 2 * DAY), ..Default::default() };
+        assert_eq!(hashes(&store, by_dates).await, ["b2"]);
+    }
+
+    #[tokio::test]
+    async fn stats_summarise_the_matching_commits() {
+        let store = store().await;
+        let query = CommitQuery { repo_id: "repo".to_string(), filter: CommitFilter::default(), limit: None };
+        let stats = store.stats(&query, 1).await.unwrap();
+
+        assert_eq!(stats.commits, 3);
+        assert_eq!(stats.active_days, 3);
+        assert_eq!(stats.first_commit_at, Some(JAN_1));
+        assert_eq!(stats.last_commit_at, Some(JAN_1 + 35 * DAY));
+
+        let weeks: Vec<_> = stats.authors_by_week.iter().map(|w| (w.week.as_str(), w.author.as_str(), w.commits)).collect();
+        assert_eq!(weeks, [("2024-W01", "Ada Lovelace", 1), ("2024-W01", "Grace Hopper", 1), ("2024-W06", "Ada Lovelace", 1)]);
+
+        assert_eq!(stats.top_paths.len(), 1);
+        assert_eq!((stats.top_paths[0].path.as_str(), stats.top_paths[0].changes), ("src/auth/login.rs", 2));
+
+        let kinds: Vec<_> = stats.kinds_by_month.iter().map(|k| (k.month.as_str(), k.kind.as_str())).collect();
+        assert_eq!(kinds, [("2024-01", "feature"), ("2024-01", "fix"), ("2024-02", "docs")]);
+    }
+
+    #[tokio::test]
+    async fn deleting_commits_leaves_other_repositories_alone() {
+        let store = store().await;
+        assert_eq!(store.commit_repo_ids().await.unwrap(), ["other", "repo"]);
+
+        assert_eq!(store.delete_commits("repo").await.unwrap(), 3);
+        assert!(hashes(&store, CommitFilter::default()).await.is_empty());
+        assert_eq!(store.commit_repo_ids().await.unwrap(), ["other"]);
+
+        let orphaned: i64 = store.conn().query_row("SELECT COUNT(*) FROM commit_files WHERE repo_id = 'repo'", [], |row| row.get(0)).unwrap();
+        assert_eq!(orphaned, 0);
+    }
+}